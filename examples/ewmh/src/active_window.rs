@@ -4,7 +4,7 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
     let display = display::open(None)?;
     let root = display.default_root_window()?;
 
-    let active = root.ewmh_get_active_window()?;
+    let active = display.use_ewmh(&root).get_active_window()?;
 
     println!("active: {:x?}", active);
 
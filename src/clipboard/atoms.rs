@@ -16,58 +16,48 @@ pub(super) struct Atoms {
     pub(super) formats: FormatAtoms,
 }
 
-#[derive(Clone, Copy)]
-pub(super) struct SelectionAtoms {
-    pub(super) clipboard: Atom,
-    pub(super) primary: Atom,
-    pub(super) secondary: Atom,
-    pub(super) clipboard_manager: Atom,
-}
-
-#[derive(Clone, Copy)]
-pub(super) struct ProtocolAtoms {
-    pub targets: Atom,          // "TARGETS"
-    pub multiple: Atom,         // "MULTIPLE"
-    pub timestamp: Atom,        // "TIMESTAMP"
-    pub target_sizes: Atom,     // "TARGET_SIZES"
-    pub save_targets: Atom,     // "SAVE_TARGETS"
-    pub delete: Atom,           // "DELETE"
-    pub insert_property: Atom,  // "INSERT_PROPERTY"
-    pub insert_selection: Atom, // "INSERT_SELECTION"
-    pub incr: Atom,             // "INCR"
-    pub atom: Atom,             // "ATOM"
-    pub none: Atom,             // "NONE"
-    pub integer: Atom,          // "INTEGER"
-}
-
-#[derive(Clone, Copy)]
-pub(super) struct FormatAtoms {
-    pub(super) utf8_string: Atom,
-    pub(super) utf8_mime: Atom,
-    pub(super) utf8_mime_alt: Atom,
-    pub(super) string: Atom,
-    pub(super) text: Atom,
-    pub(super) plain: Atom,
-    pub(super) html: Atom,
-    pub(super) rtf: Atom,
-    pub(super) png: Atom,
-    pub(super) jpeg: Atom,
-    pub(super) tiff: Atom,
-    pub(super) bmp: Atom,
-    pub(super) pdf: Atom,
-    pub(super) uri_list: Atom,
+crate::atoms! {
+    pub(super) struct SelectionAtoms {
+        pub(super) clipboard: "CLIPBOARD",
+        pub(super) primary: "PRIMARY",
+        pub(super) secondary: "SECONDARY",
+        pub(super) clipboard_manager: "CLIPBOARD_MANAGER",
+    }
 }
 
-trait InternAtom {
-    fn intern(&self, name: &str, only_if_exists: bool) -> Result<Atom, Error>;
+crate::atoms! {
+    pub(super) struct ProtocolAtoms {
+        pub targets: "TARGETS",
+        pub multiple: "MULTIPLE",
+        pub timestamp: "TIMESTAMP",
+        pub target_sizes: "TARGET_SIZES",
+        pub save_targets: "SAVE_TARGETS",
+        pub delete: "DELETE",
+        pub insert_property: "INSERT_PROPERTY",
+        pub insert_selection: "INSERT_SELECTION",
+        pub incr: "INCR",
+        pub atom: "ATOM",
+        pub none: "NONE",
+        pub integer: "INTEGER",
+    }
 }
 
-impl InternAtom for Display {
-    fn intern(&self, name: &str, only_if_exists: bool) -> Result<Atom, Error> {
-        let atom = self.intern_atom(name, only_if_exists)?;
-        let mut guard = NAMES.write().map_err(|e| Error::RwLock(e.to_string()))?;
-        guard.insert(atom, name.to_string());
-        Ok(atom)
+crate::atoms! {
+    pub(super) struct FormatAtoms {
+        pub(super) utf8_string: "UTF8_STRING",
+        pub(super) utf8_mime: "text/plain;charset=utf-8",
+        pub(super) utf8_mime_alt: "text/plain;charset=utf8",
+        pub(super) string: "STRING",
+        pub(super) text: "TEXT",
+        pub(super) plain: "text/plain",
+        pub(super) html: "text/html",
+        pub(super) rtf: "text/rtf",
+        pub(super) png: "image/png",
+        pub(super) jpeg: "image/jpeg",
+        pub(super) tiff: "image/tiff",
+        pub(super) bmp: "image/bmp",
+        pub(super) pdf: "application/pdf",
+        pub(super) uri_list: "text/uri-list",
     }
 }
 
@@ -95,11 +85,25 @@ impl AtomName for Atom {
 
 impl Atoms {
     pub(super) fn new(display: &Display) -> Result<Atoms, Error> {
-        Ok(Atoms {
+        let atoms = Atoms {
             selections: SelectionAtoms::new(display)?,
             protocol: ProtocolAtoms::new(display)?,
             formats: FormatAtoms::new(display)?,
-        })
+        };
+
+        let mut guard = NAMES.write().map_err(|e| Error::RwLock(e.to_string()))?;
+
+        for (atom, name) in atoms
+            .selections
+            .names()
+            .into_iter()
+            .chain(atoms.protocol.names())
+            .chain(atoms.formats.names())
+        {
+            guard.insert(atom, name.to_string());
+        }
+
+        Ok(atoms)
     }
 
     pub(super) fn is_side_effect_target(&self, target: Atom) -> bool {
@@ -111,54 +115,3 @@ impl Atoms {
             || target == self.protocol.incr
     }
 }
-
-impl SelectionAtoms {
-    fn new(display: &Display) -> Result<Self, Error> {
-        Ok(Self {
-            clipboard: display.intern("CLIPBOARD", false)?,
-            primary: display.intern("PRIMARY", false)?,
-            secondary: display.intern("SECONDARY", false)?,
-            clipboard_manager: display.intern("CLIPBOARD_MANAGER", false)?,
-        })
-    }
-}
-
-impl ProtocolAtoms {
-    fn new(display: &Display) -> Result<Self, Error> {
-        Ok(Self {
-            targets: display.intern("TARGETS", false)?,
-            multiple: display.intern("MULTIPLE", false)?,
-            timestamp: display.intern("TIMESTAMP", false)?,
-            target_sizes: display.intern("TARGET_SIZES", false)?,
-            save_targets: display.intern("SAVE_TARGETS", false)?,
-            delete: display.intern("DELETE", false)?,
-            insert_property: display.intern("INSERT_PROPERTY", false)?,
-            insert_selection: display.intern("INSERT_SELECTION", false)?,
-            incr: display.intern("INCR", false)?,
-            atom: display.intern("ATOM", false)?,
-            none: display.intern("NONE", false)?,
-            integer: display.intern("INTEGER", false)?,
-        })
-    }
-}
-
-impl FormatAtoms {
-    fn new(display: &Display) -> Result<Self, Error> {
-        Ok(Self {
-            utf8_string: display.intern("UTF8_STRING", false)?,
-            utf8_mime: display.intern("text/plain;charset=utf-8", false)?,
-            utf8_mime_alt: display.intern("text/plain;charset=utf8", false)?,
-            string: display.intern("STRING", false)?,
-            text: display.intern("TEXT", false)?,
-            plain: display.intern("text/plain", false)?,
-            html: display.intern("text/html", false)?,
-            rtf: display.intern("text/rtf", false)?,
-            png: display.intern("image/png", false)?,
-            jpeg: display.intern("image/jpeg", false)?,
-            tiff: display.intern("image/tiff", false)?,
-            bmp: display.intern("image/bmp", false)?,
-            pdf: display.intern("application/pdf", false)?,
-            uri_list: display.intern("text/uri-list", false)?,
-        })
-    }
-}
@@ -132,7 +132,7 @@ impl Context {
                 return Err(Error::InvalidData("Expected INCR property".into()));
             }
 
-            let size = u32::from_ne_bytes(size_data[..4].try_into().unwrap()) as usize;
+            let size = u32::from_le_bytes(size_data[..4].try_into().unwrap()) as usize;
             let mut buffer = Vec::with_capacity(size);
 
             // read data chunks
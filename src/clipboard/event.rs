@@ -6,8 +6,9 @@ use std::thread::{self, JoinHandle};
 use std::time::{Duration, Instant};
 
 use crate::clipboard::atoms::AtomName;
+use crate::display::error::Error as DisplayError;
 use crate::display::{Atom, Display};
-use crate::proto::Event;
+use crate::proto::{Event, Signal};
 use crate::window::{PropFormat, PropMode, Window};
 
 use super::atoms::Atoms;
@@ -19,6 +20,8 @@ const MAX_REGULAR_SIZE: usize = 65536;
 const INCR_CHUNK_SIZE: usize = 4096;
 const TRANSFER_TIMEOUT: Duration = Duration::from_secs(5);
 const POLL_INTERVAL: Duration = Duration::from_millis(10);
+// the event loop checks if it was stopped at least this often, in case the wake up is missed
+const WAKEUP_INTERVAL: Duration = Duration::from_millis(100);
 
 #[derive(Default)]
 pub(super) struct EventLoop {
@@ -26,6 +29,7 @@ pub(super) struct EventLoop {
     events: Arc<Mutex<VecDeque<Event>>>,
     condvar: Arc<Condvar>,
     handle: Mutex<Option<JoinHandle<Result<(), Error>>>>,
+    signal: Mutex<Option<Signal>>,
 }
 
 impl EventLoop {
@@ -38,30 +42,33 @@ impl EventLoop {
         let condvar = self.condvar.clone();
         let killed = self.killed.clone();
 
-        let handle = thread::spawn(move || {
-            // TODO: this event polling can be even more performant if we use condvar instead of a
-            // poll interval
+        // stopping the loop wakes up the display so the thread does not wait for the next event
+        let mut signal = self.signal.lock().map_err(|e| Error::Lock(e.to_string()))?;
+
+        *signal = Some(display.signal.clone());
 
+        let handle = thread::spawn(move || {
             while !killed.load(Ordering::Relaxed) {
-                match display.poll_event() {
-                    Ok(true) => {
-                        let mut events = events.lock().map_err(|e| Error::Lock(e.to_string()))?;
+                let result = display
+                    .wait_for_activity(Some(WAKEUP_INTERVAL))
+                    .and_then(|_| {
+                        while let Some(event) = display.try_next_event()? {
+                            let mut events =
+                                events.lock().map_err(|_| DisplayError::FailedToLock)?;
 
-                        if let Ok(event) = display.next_event() {
                             events.push_back(event);
                             condvar.notify_all();
                         }
-                    }
-                    Ok(false) => {
-                        thread::sleep(POLL_INTERVAL);
-                    }
-                    Err(e) => {
-                        if killed.load(Ordering::Relaxed) {
-                            return Ok(());
-                        }
 
-                        return Err(Error::EventLoopError(format!("Event source error: {}", e)));
+                        Ok(())
+                    });
+
+                if let Err(e) = result {
+                    if killed.load(Ordering::Relaxed) {
+                        return Ok(());
                     }
+
+                    return Err(Error::EventLoopError(format!("Event source error: {}", e)));
                 }
             }
             Ok(())
@@ -94,6 +101,10 @@ impl EventLoop {
         self.killed.store(true, Ordering::Relaxed);
         self.condvar.notify_all();
 
+        if let Some(signal) = self.signal.lock().map_err(|e| Error::Lock(e.to_string()))?.as_ref() {
+            signal.notify()?;
+        }
+
         if let Some(handle) = self.handle.lock().map_err(|e| Error::Lock(e.to_string()))?.take() {
             return handle.join().map_err(|_| Error::Terminated)?;
         }
@@ -340,7 +351,7 @@ impl EventHandler {
     pub fn set_targets(&self, selection: Atom, targets: Vec<Atom>) -> Result<(), Error> {
         let bytes = targets
             .iter()
-            .flat_map(|&t| t.to_ne_bytes().to_vec())
+            .flat_map(|&t| t.to_le_bytes().to_vec())
             .collect::<Vec<_>>();
 
         let data = ClipboardData::new(bytes, self.state.atoms.protocol.targets);
@@ -533,7 +544,7 @@ impl EventHandler {
             )? {
                 if actual_type == state.atoms.protocol.incr {
                     // INCR transfer
-                    let size = u32::from_ne_bytes(data[..4].try_into().unwrap());
+                    let size = u32::from_le_bytes(data[..4].try_into().unwrap());
                     if let Some(data) =
                         state
                             .context
@@ -581,7 +592,7 @@ impl EventHandler {
             let targets = state.cache.get_targets(selection)?;
             let mut data = Vec::with_capacity(targets.len() * 4);
             for target in targets {
-                data.extend_from_slice(&target.to_ne_bytes());
+                data.extend_from_slice(&target.to_le_bytes());
             }
 
             window.change_property(
@@ -663,7 +674,7 @@ impl EventHandler {
                 state.atoms.protocol.incr,
                 PropFormat::Format32,
                 PropMode::Replace,
-                &size.to_ne_bytes(),
+                &size.to_le_bytes(),
             )?;
 
             // chunked transfer
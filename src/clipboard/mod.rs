@@ -471,8 +471,8 @@ impl Clipboard {
         {
             let bytes = data.bytes();
             for i in (0..bytes.len()).step_by(4) {
-                let ne_bytes = (&bytes[i..i + 4]).try_into().unwrap();
-                let target = Atom::from_ne_bytes(ne_bytes);
+                let le_bytes = (&bytes[i..i + 4]).try_into().unwrap();
+                let target = Atom::from_le_bytes(le_bytes);
                 targets.push(target);
             }
         }
@@ -489,8 +489,8 @@ impl Clipboard {
         {
             let bytes = data.bytes();
             for i in (0..bytes.len()).step_by(4) {
-                let ne_bytes = (&bytes[i..i + 4]).try_into().unwrap();
-                let atom = Atom::from_ne_bytes(ne_bytes);
+                let le_bytes = (&bytes[i..i + 4]).try_into().unwrap();
+                let atom = Atom::from_le_bytes(le_bytes);
                 let name = atom.name().unwrap_or_else(|| {
                     self.context.get_atom_name(atom).unwrap_or(atom.to_string())
                 });
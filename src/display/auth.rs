@@ -1,60 +1,432 @@
-use super::{Error, Stream};
+use super::Error;
 
 use std::env;
-use std::fs::File;
-use std::mem;
-use std::sync::{Arc, Mutex};
+use std::fs::{self, File};
+use std::io::{self, Read, Write};
+use std::net::IpAddr;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::PathBuf;
 
-#[derive(Debug)]
+/// the name of the only authorization protocol most servers support
+pub const MIT_MAGIC_COOKIE_1: &str = "MIT-MAGIC-COOKIE-1";
+
+/// the length of a MIT-MAGIC-COOKIE-1 cookie in bytes
+const COOKIE_LEN: usize = 16;
+
+/// the address families used in the xauthority file
+#[non_exhaustive]
+pub struct Family;
+
+impl Family {
+    pub const INTERNET: u16 = 0;
+    pub const INTERNET_V6: u16 = 6;
+    pub const LOCAL: u16 = 256;
+    pub const WILD: u16 = 65535;
+}
+
+#[derive(Debug, Clone, PartialEq)]
 pub struct Entry {
-    family: u16,
-    address: Vec<u8>,
-    number: Vec<u8>,
+    pub family: u16,
+    pub address: Vec<u8>,
+    pub number: Vec<u8>,
     pub name: Vec<u8>,
     pub data: Vec<u8>,
 }
 
-pub struct XAuth {
-    file: Stream,
+impl Entry {
+    pub fn new(address: &Address, display: u16, name: &[u8], data: &[u8]) -> Entry {
+        Entry {
+            family: address.family,
+            address: address.address.clone(),
+            number: display.to_string().into_bytes(),
+            name: name.to_vec(),
+            data: data.to_vec(),
+        }
+    }
+
+    /// create a MIT-MAGIC-COOKIE-1 entry with a new random cookie
+    pub fn mit_magic_cookie(address: &Address, display: u16) -> Result<Entry, Error> {
+        Ok(Entry::new(
+            address,
+            display,
+            MIT_MAGIC_COOKIE_1.as_bytes(),
+            &generate_cookie()?,
+        ))
+    }
+
+    /// check if the entry is for the provided display number, an empty number matches all displays
+    fn matches_display(&self, display: u16) -> bool {
+        self.number.is_empty() || self.number == display.to_string().as_bytes()
+    }
+
+    /// check if the client can authorize with the protocol of the entry
+    fn is_supported(&self) -> bool {
+        self.name == MIT_MAGIC_COOKIE_1.as_bytes()
+    }
+
+    /// check if the entry is for the same server and protocol as another entry
+    fn same_key(&self, rhs: &Entry) -> bool {
+        self.family == rhs.family
+            && self.address == rhs.address
+            && self.number == rhs.number
+            && self.name == rhs.name
+    }
+
+    /// append the entry as it is stored in the xauthority file
+    pub fn encode(&self, bytes: &mut Vec<u8>) {
+        bytes.extend_from_slice(&self.family.to_be_bytes());
+
+        for value in [&self.address, &self.number, &self.name, &self.data] {
+            bytes.extend_from_slice(&(value.len() as u16).to_be_bytes());
+            bytes.extend_from_slice(value);
+        }
+    }
 }
 
-impl XAuth {
-    pub fn new() -> Result<XAuth, Box<dyn std::error::Error>> {
-        let file = File::options()
-            .read(true)
-            .write(true)
-            .open(env::var("XAUTHORITY")?)?;
+/// generate a random MIT-MAGIC-COOKIE-1 cookie
+pub fn generate_cookie() -> Result<[u8; COOKIE_LEN], Error> {
+    let mut cookie = [0u8; COOKIE_LEN];
 
-        Ok(XAuth {
-            file: Stream::new(
-                Arc::new(Mutex::new(file.try_clone()?)),
-                Arc::new(Mutex::new(file)),
-            ),
-        })
+    File::open("/dev/urandom").and_then(|mut file| file.read_exact(&mut cookie))?;
+
+    Ok(cookie)
+}
+
+/// the address of the server as it is written in the xauthority file
+#[derive(Debug, Clone, PartialEq)]
+pub struct Address {
+    pub family: u16,
+    pub address: Vec<u8>,
+}
+
+impl Address {
+    /// the address used for connections to the local machine, this is the hostname
+    pub fn local() -> Address {
+        Address {
+            family: Family::LOCAL,
+            address: hostname().into_bytes(),
+        }
     }
 
-    fn value(&mut self) -> Result<Vec<u8>, Error> {
-        let size = self.file.recv(mem::size_of::<u16>())?;
+    /// the address used for tcp connections, connections to the loopback address are local
+    pub fn from_ip(ip: IpAddr) -> Address {
+        match ip {
+            ip if ip.is_loopback() => Address::local(),
+            IpAddr::V4(ip) => Address {
+                family: Family::INTERNET,
+                address: ip.octets().to_vec(),
+            },
+            IpAddr::V6(ip) => match ip.to_ipv4_mapped() {
+                Some(ip) => Address::from_ip(IpAddr::V4(ip)),
+                None => Address {
+                    family: Family::INTERNET_V6,
+                    address: ip.octets().to_vec(),
+                },
+            },
+        }
+    }
+}
 
-        self.file
-            .recv(((size[0] as u16) << 8 | size[1] as u16) as usize)
+fn hostname() -> String {
+    ["/proc/sys/kernel/hostname", "/etc/hostname"]
+        .iter()
+        .find_map(|path| fs::read_to_string(path).ok())
+        .or_else(|| env::var("HOSTNAME").ok())
+        .map(|hostname| hostname.trim().to_string())
+        .unwrap_or_default()
+}
+
+/// the xauthority file from $XAUTHORITY, or ~/.Xauthority if it is not set
+pub fn path() -> Option<PathBuf> {
+    match env::var_os("XAUTHORITY") {
+        Some(path) => Some(PathBuf::from(path)),
+        None => env::var_os("HOME").map(|home| PathBuf::from(home).join(".Xauthority")),
     }
+}
 
-    pub fn entry(&mut self) -> Result<Entry, Error> {
-        let family = self.file.recv(mem::size_of::<u16>())?;
+fn take<'a>(bytes: &mut &'a [u8], size: usize) -> Option<&'a [u8]> {
+    let value = bytes.get(..size)?;
+
+    *bytes = &bytes[size..];
+
+    Some(value)
+}
+
+fn take_u16(bytes: &mut &[u8]) -> Option<u16> {
+    take(bytes, 2).map(|value| u16::from_be_bytes([value[0], value[1]]))
+}
+
+fn take_value(bytes: &mut &[u8]) -> Option<Vec<u8>> {
+    let size = take_u16(bytes)?;
+
+    take(bytes, size as usize).map(|value| value.to_vec())
+}
+
+fn parse_entry(bytes: &mut &[u8]) -> Option<Entry> {
+    Some(Entry {
+        family: take_u16(bytes)?,
+        address: take_value(bytes)?,
+        number: take_value(bytes)?,
+        name: take_value(bytes)?,
+        data: take_value(bytes)?,
+    })
+}
+
+/// parse all entries of an xauthority file, the values are stored big endian regardless of the
+/// machine, a truncated entry at the end of the file is ignored
+pub fn parse(mut bytes: &[u8]) -> Vec<Entry> {
+    let mut entries = Vec::new();
+
+    while let Some(entry) = parse_entry(&mut bytes) {
+        entries.push(entry);
+    }
+
+    entries
+}
+
+/// find the entry for the address and display number, an entry for the exact address is
+/// preferred over a wildcard entry, entries for protocols the client does not support such as
+/// XDM-AUTHORIZATION-1 are skipped
+pub fn find(entries: Vec<Entry>, address: &Address, display: u16) -> Option<Entry> {
+    let mut wild = None;
+
+    for entry in entries
+        .into_iter()
+        .filter(|entry| entry.matches_display(display) && entry.is_supported())
+    {
+        if entry.family == address.family && entry.address == address.address {
+            return Some(entry);
+        } else if entry.family == Family::WILD && wild.is_none() {
+            wild = Some(entry);
+        }
+    }
+
+    wild
+}
+
+/// read and parse an xauthority file, a missing file has no entries
+fn read(path: &PathBuf) -> Result<Vec<Entry>, Error> {
+    match fs::read(path) {
+        Ok(bytes) => Ok(parse(&bytes)),
+        Err(err) if err.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(err) => Err(err.into()),
+    }
+}
+
+/// get the entry for the address and display number, no entry is returned if there is no
+/// xauthority file
+pub fn entry(address: &Address, display: u16) -> Result<Option<Entry>, Error> {
+    match path() {
+        Some(path) => Ok(find(read(&path)?, address, display)),
+        None => Ok(None),
+    }
+}
+
+/// an xauthority file that can be edited, changes are only written when the file is saved
+pub struct XAuthority {
+    path: PathBuf,
+    entries: Vec<Entry>,
+}
 
-        Ok(Entry {
-            family: (family[0] as u16) << 8 | family[1] as u16,
-            address: self.value()?,
-            number: self.value()?,
-            name: self.value()?,
-            data: self.value()?,
+impl XAuthority {
+    /// open an xauthority file, a file that does not exist yet has no entries
+    pub fn open<P: Into<PathBuf>>(path: P) -> Result<XAuthority, Error> {
+        let path = path.into();
+
+        Ok(XAuthority {
+            entries: read(&path)?,
+            path,
         })
     }
+
+    /// open the xauthority file from $XAUTHORITY, or ~/.Xauthority if it is not set
+    pub fn open_default() -> Result<XAuthority, Error> {
+        let path = path().ok_or_else(|| {
+            io::Error::new(
+                io::ErrorKind::NotFound,
+                "neither $XAUTHORITY nor $HOME is set",
+            )
+        })?;
+
+        XAuthority::open(path)
+    }
+
+    /// list all entries
+    pub fn entries(&self) -> &[Entry] {
+        &self.entries
+    }
+
+    /// add an entry, this replaces an existing entry for the same address, display and protocol
+    pub fn add(&mut self, entry: Entry) {
+        match self.entries.iter_mut().find(|other| other.same_key(&entry)) {
+            Some(other) => *other = entry,
+            None => self.entries.push(entry),
+        }
+    }
+
+    /// remove all entries for the address and display number, returns how many were removed
+    pub fn remove(&mut self, address: &Address, display: u16) -> usize {
+        let len = self.entries.len();
+
+        self.entries.retain(|entry| {
+            entry.family != address.family
+                || entry.address != address.address
+                || entry.number != display.to_string().as_bytes()
+        });
+
+        len - self.entries.len()
+    }
+
+    /// write the entries to the file, a temporary file is renamed over the old file so readers
+    /// never see a partially written file
+    pub fn save(&self) -> Result<(), Error> {
+        let mut bytes = Vec::new();
+
+        self.entries
+            .iter()
+            .for_each(|entry| entry.encode(&mut bytes));
+
+        let mut name = self
+            .path
+            .file_name()
+            .ok_or_else(|| io::Error::new(io::ErrorKind::InvalidInput, "path has no file name"))?
+            .to_os_string();
+
+        name.push("-n");
+
+        let temporary = self.path.with_file_name(name);
+
+        File::options()
+            .write(true)
+            .create(true)
+            .truncate(true)
+            .mode(0o600)
+            .open(&temporary)
+            .and_then(|mut file| {
+                file.write_all(&bytes)?;
+                file.sync_all()
+            })
+            .and_then(|_| fs::rename(&temporary, &self.path))
+            .map_err(|err| {
+                let _ = fs::remove_file(&temporary);
+
+                Error::from(err)
+            })
+    }
 }
 
-pub fn entry() -> Result<Entry, Error> {
-    let mut auth = XAuth::new().map_err(|_| Error::Stream)?;
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn entry(family: u16, address: &[u8], number: &str, data: &[u8]) -> Entry {
+        Entry {
+            family,
+            address: address.to_vec(),
+            number: number.as_bytes().to_vec(),
+            name: MIT_MAGIC_COOKIE_1.as_bytes().to_vec(),
+            data: data.to_vec(),
+        }
+    }
+
+    #[test]
+    fn test_parse() {
+        let entries = vec![
+            entry(Family::LOCAL, b"host", "0", &[1; 16]),
+            entry(Family::INTERNET, &[10, 0, 0, 1], "1", &[2; 16]),
+        ];
+
+        let mut bytes = Vec::new();
+
+        entries.iter().for_each(|entry| entry.encode(&mut bytes));
+
+        assert_eq!(parse(&bytes), entries);
+
+        bytes.pop();
 
-    auth.entry()
+        assert_eq!(parse(&bytes), entries[..1]);
+    }
+
+    #[test]
+    fn test_find() {
+        let local = Address {
+            family: Family::LOCAL,
+            address: b"host".to_vec(),
+        };
+
+        let entries = vec![
+            entry(Family::WILD, b"", "", &[0; 16]),
+            entry(Family::LOCAL, b"other", "0", &[1; 16]),
+            entry(Family::LOCAL, b"host", "1", &[2; 16]),
+            entry(Family::LOCAL, b"host", "0", &[3; 16]),
+        ];
+
+        assert_eq!(find(entries.clone(), &local, 0), Some(entries[3].clone()));
+        assert_eq!(find(entries.clone(), &local, 1), Some(entries[2].clone()));
+        assert_eq!(find(entries.clone(), &local, 2), Some(entries[0].clone()));
+        assert_eq!(find(entries[1..].to_vec(), &local, 2), None);
+
+        let remote = Address::from_ip(IpAddr::from([10, 0, 0, 1]));
+
+        assert_eq!(remote.family, Family::INTERNET);
+        assert_eq!(find(entries.clone(), &remote, 0), Some(entries[0].clone()));
+        assert_eq!(
+            Address::from_ip(IpAddr::from([127, 0, 0, 1])),
+            Address::local()
+        );
+    }
+
+    #[test]
+    fn test_find_skips_unsupported() {
+        let local = Address {
+            family: Family::LOCAL,
+            address: b"host".to_vec(),
+        };
+
+        let mut xdm = entry(Family::LOCAL, b"host", "0", &[1; 8]);
+
+        xdm.name = b"XDM-AUTHORIZATION-1".to_vec();
+
+        let entries = vec![xdm.clone(), entry(Family::LOCAL, b"host", "0", &[2; 16])];
+
+        assert_eq!(find(entries.clone(), &local, 0), Some(entries[1].clone()));
+        assert_eq!(find(vec![xdm], &local, 0), None);
+    }
+
+    #[test]
+    fn test_xauthority() -> Result<(), Error> {
+        let path = env::temp_dir().join(format!("yaxi-test-xauthority-{}", std::process::id()));
+
+        let local = Address::local();
+
+        let mut xauthority = XAuthority::open(&path)?;
+
+        assert!(xauthority.entries().is_empty());
+
+        let first = Entry::mit_magic_cookie(&local, 0)?;
+        let second = Entry::mit_magic_cookie(&local, 0)?;
+
+        assert_eq!(first.data.len(), COOKIE_LEN);
+        assert_ne!(first.data, second.data);
+
+        xauthority.add(first);
+        xauthority.add(second.clone());
+        xauthority.add(Entry::mit_magic_cookie(&local, 1)?);
+
+        // an entry for the same display replaces the old one
+        assert_eq!(xauthority.entries().len(), 2);
+
+        xauthority.save()?;
+
+        assert_eq!(XAuthority::open(&path)?.entries(), xauthority.entries());
+        assert_eq!(find(read(&path)?, &local, 0), Some(second));
+
+        assert_eq!(xauthority.remove(&local, 1), 1);
+
+        xauthority.save()?;
+
+        assert_eq!(XAuthority::open(&path)?.entries().len(), 1);
+
+        Ok(fs::remove_file(&path)?)
+    }
 }
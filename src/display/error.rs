@@ -9,10 +9,19 @@ pub enum Error {
     Authenthicate,
     RanOutOfXid,
     NoScreens,
+    InvalidScreen {
+        screen: usize,
+    },
     NoReply,
+    ReplyDiscarded {
+        sequence: u64,
+    },
+    ConnectionClosed,
     FailedToLock,
     FailedToWait,
-    Stream,
+    Stream {
+        error: std::io::Error,
+    },
     Utf8,
     InvalidDisplay,
     InvalidProtocol {
@@ -21,12 +30,30 @@ pub enum Error {
     SetupFailed {
         reason: String,
     },
+    Protocol {
+        reason: String,
+    },
+    InvalidValue {
+        name: &'static str,
+        value: u32,
+    },
+    UnexpectedReply,
+    RequestTooLarge {
+        length: usize,
+        maximum: usize,
+    },
+    PropertyMismatch {
+        type_: u32,
+        format: u8,
+    },
     Event {
         error: ErrorCode,
         major_opcode: u8,
         minor_opcode: u16,
         bad_value: u32,
         sequence: u16,
+        request: Option<&'static str>,
+        extension: Option<String>,
     },
     Other {
         error: Box<dyn std::error::Error + Send + Sync>,
@@ -50,10 +77,18 @@ impl std::fmt::Display for Error {
             Error::Authenthicate => f.write_str("authenthication required"),
             Error::RanOutOfXid => f.write_str("server ran out of xid's"),
             Error::NoScreens => f.write_str("server never informed of any screens"),
+            Error::InvalidScreen { screen } => {
+                f.write_fmt(format_args!("server has no screen {}", screen))
+            }
             Error::NoReply => f.write_str("reply queue empty"),
+            Error::ReplyDiscarded { sequence } => f.write_fmt(format_args!(
+                "the server responded to a later request before the reply to request {} arrived",
+                sequence
+            )),
+            Error::ConnectionClosed => f.write_str("connection to the server is closed"),
             Error::FailedToLock => f.write_str("failed to lock mutex"),
             Error::FailedToWait => f.write_str("failed to wait for condvar"),
-            Error::Stream => f.write_str("stream failed"),
+            Error::Stream { error } => f.write_fmt(format_args!("stream failed: {}", error)),
             Error::Utf8 => f.write_str("invalid utf8"),
             Error::InvalidDisplay => f.write_str("invalid $DISPLAY format"),
             Error::InvalidProtocol { protocol } => {
@@ -63,19 +98,162 @@ impl std::fmt::Display for Error {
                 "connection initiation setup failed: {}",
                 reason
             )),
+            Error::Protocol { reason } => {
+                f.write_fmt(format_args!("server violated the protocol: {}", reason))
+            }
+            Error::InvalidValue { name, value } => f.write_fmt(format_args!(
+                "server sent invalid value {} for {}",
+                value, name
+            )),
+            Error::UnexpectedReply => f.write_str("server sent a reply of the wrong kind"),
+            Error::RequestTooLarge { length, maximum } => f.write_fmt(format_args!(
+                "request of {} bytes is larger than the maximum of {} bytes",
+                length, maximum
+            )),
+            Error::PropertyMismatch { type_, format } => f.write_fmt(format_args!(
+                "property with type {} and format {} does not match the requested value",
+                type_, format
+            )),
             Error::Event {
                 error,
                 major_opcode,
                 minor_opcode,
                 bad_value,
                 sequence,
-            } => f.write_fmt(format_args!(
-                "error={:?}, major_opcode={}, minor_opcode={}, bad_value={}, sequence={}",
-                error, major_opcode, minor_opcode, bad_value, sequence
-            )),
+                request,
+                extension,
+            } => {
+                f.write_fmt(format_args!("{:?} error for request ", error))?;
+
+                match (request, extension) {
+                    (Some(request), _) => f.write_str(request)?,
+                    (None, Some(extension)) => {
+                        f.write_fmt(format_args!("{} of extension {}", minor_opcode, extension))?
+                    }
+                    (None, None) => f.write_str("unknown")?,
+                }
+
+                f.write_fmt(format_args!(
+                    " (major_opcode={}, minor_opcode={}), bad_value={}, sequence={}",
+                    major_opcode, minor_opcode, bad_value, sequence
+                ))
+            }
             Error::Other { error } => f.write_fmt(format_args!("other: {}", error)),
         }
     }
 }
 
-impl std::error::Error for Error {}
+impl std::error::Error for Error {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            Error::Stream { error } => Some(error),
+            Error::Other { error } => Some(error.as_ref()),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for Error {
+    fn from(error: std::io::Error) -> Error {
+        Error::Stream { error }
+    }
+}
+
+macro_rules! predicates {
+    ($($name:ident => $code:ident),*) => {
+        $(
+            #[doc = concat!("returns true if the server sent a ", stringify!($code), " error")]
+            pub fn $name(&self) -> bool {
+                self.error_code() == Some(ErrorCode::$code)
+            }
+        )*
+    };
+}
+
+impl Error {
+    /// the error code if the server sent an error for a request
+    pub fn error_code(&self) -> Option<ErrorCode> {
+        match self {
+            Error::Event { error, .. } => Some(*error),
+            _ => None,
+        }
+    }
+
+    predicates!(
+        is_bad_request => Request,
+        is_bad_value => Value,
+        is_bad_window => Window,
+        is_bad_pixmap => Pixmap,
+        is_bad_atom => Atom,
+        is_bad_cursor => Cursor,
+        is_bad_font => Font,
+        is_bad_match => Match,
+        is_bad_drawable => Drawable,
+        is_bad_access => Access,
+        is_bad_alloc => Alloc,
+        is_bad_colormap => Colormap,
+        is_bad_gcontext => GContext,
+        is_bad_id_choice => IdChoice,
+        is_bad_name => Name,
+        is_bad_length => Length,
+        is_bad_implementation => Implementation
+    );
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::proto::Opcode;
+
+    use std::error::Error as _;
+
+    fn event(major_opcode: u8, extension: Option<&str>) -> Error {
+        Error::Event {
+            error: ErrorCode::Window,
+            major_opcode,
+            minor_opcode: 3,
+            bad_value: 0x200001,
+            sequence: 7,
+            request: Opcode::name(major_opcode),
+            extension: extension.map(String::from),
+        }
+    }
+
+    #[test]
+    fn test_event_errors() {
+        let error = event(Opcode::CONFIGURE_WINDOW, None);
+
+        assert!(error.is_bad_window());
+        assert!(!error.is_bad_match());
+        assert_eq!(error.error_code(), Some(ErrorCode::Window));
+        assert_eq!(
+            error.to_string(),
+            "Window error for request ConfigureWindow (major_opcode=12, minor_opcode=3), \
+             bad_value=2097153, sequence=7"
+        );
+
+        assert!(event(140, Some("XINERAMA"))
+            .to_string()
+            .starts_with("Window error for request 3 of extension XINERAMA"));
+
+        assert!(!Error::NoReply.is_bad_window());
+        assert!(Error::ReplyDiscarded { sequence: 9 }
+            .to_string()
+            .ends_with("before the reply to request 9 arrived"));
+    }
+
+    #[test]
+    fn test_stream_error_source() {
+        let error = Error::from(std::io::Error::from(std::io::ErrorKind::BrokenPipe));
+
+        assert_eq!(
+            error
+                .source()
+                .and_then(|source| source.downcast_ref::<std::io::Error>())
+                .map(|source| source.kind()),
+            Some(std::io::ErrorKind::BrokenPipe)
+        );
+        assert!(error.to_string().starts_with("stream failed: "));
+    }
+}
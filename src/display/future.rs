@@ -0,0 +1,317 @@
+//! futures for replies and events, they are woken by the listener thread so they work with any
+//! executor and never block it
+//!
+//! with a single threaded connection nothing reads the responses in the background, the futures
+//! only complete once another thread waits on the display or calls [`Display::try_next_event`]
+//!
+//! [`Display::try_next_event`]: super::Display::try_next_event
+
+use super::error::Error;
+use super::Stream;
+
+use crate::proto::{Event, Queue, Replies, Reply};
+
+use std::future::Future;
+use std::pin::Pin;
+use std::task::{Context, Poll};
+
+/// a future that resolves to the reply of a request, the reply is dropped when it arrives if the
+/// future was dropped first
+pub struct ReplyFuture {
+    replies: Replies,
+    sequence: u64,
+    done: bool,
+}
+
+impl ReplyFuture {
+    pub(crate) fn new(replies: Replies, sequence: u64) -> ReplyFuture {
+        ReplyFuture {
+            replies,
+            sequence,
+            done: false,
+        }
+    }
+}
+
+impl Future for ReplyFuture {
+    type Output = Result<Reply, Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        let poll = self.replies.poll_wait(self.sequence, cx);
+
+        self.done = poll.is_ready();
+
+        poll
+    }
+}
+
+impl Drop for ReplyFuture {
+    fn drop(&mut self) {
+        if !self.done {
+            let _ = self.replies.abandon(self.sequence);
+        }
+    }
+}
+
+/// a stream of events that ends when the connection is closed
+///
+/// this has the same shape as the `Stream` trait from the futures crate, so an adapter only has
+/// to forward [`EventStream::poll_next`]
+pub struct EventStream {
+    stream: Stream,
+    events: Queue<Event>,
+}
+
+impl EventStream {
+    pub(crate) fn new(stream: Stream, events: Queue<Event>) -> EventStream {
+        EventStream { stream, events }
+    }
+
+    /// poll for the next event, the output buffer is flushed first so requests that cause
+    /// events reach the server
+    pub fn poll_next(
+        self: Pin<&mut Self>,
+        cx: &mut Context<'_>,
+    ) -> Poll<Option<Result<Event, Error>>> {
+        let poll = match self.stream.flush() {
+            Ok(()) => self.events.poll_pop(cx),
+            Err(error) => Poll::Ready(Err(error)),
+        };
+
+        match poll {
+            Poll::Ready(Err(Error::ConnectionClosed)) => Poll::Ready(None),
+            Poll::Ready(result) => Poll::Ready(Some(result)),
+            Poll::Pending => Poll::Pending,
+        }
+    }
+
+    /// wait for the next event, none is returned once the connection is closed
+    pub fn next_event(&mut self) -> NextEvent<'_> {
+        NextEvent { events: self }
+    }
+}
+
+/// the future returned by [`EventStream::next_event`]
+pub struct NextEvent<'a> {
+    events: &'a mut EventStream,
+}
+
+impl Future for NextEvent<'_> {
+    type Output = Option<Result<Event, Error>>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut Context<'_>) -> Poll<Self::Output> {
+        Pin::new(&mut *self.events).poll_next(cx)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::proto::Signal;
+
+    use std::fs::File;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::{Arc, Mutex};
+    use std::task::{Wake, Waker};
+    use std::thread::{self, Thread};
+
+    struct ThreadWaker(Thread);
+
+    impl Wake for ThreadWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.unpark();
+        }
+    }
+
+    /// a waker that counts how often it was woken up
+    #[derive(Default)]
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::SeqCst);
+        }
+    }
+
+    /// a minimal executor, the thread is parked until the future is woken
+    fn block_on<F: Future>(future: F) -> F::Output {
+        let waker = Waker::from(Arc::new(ThreadWaker(thread::current())));
+        let mut cx = Context::from_waker(&waker);
+        let mut future = std::pin::pin!(future);
+
+        loop {
+            match future.as_mut().poll(&mut cx) {
+                Poll::Ready(output) => return output,
+                Poll::Pending => thread::park(),
+            }
+        }
+    }
+
+    fn stream() -> Result<Stream, Error> {
+        let file = File::options().read(true).write(true).open("/dev/null")?;
+
+        Ok(Stream::new(
+            Arc::new(Mutex::new(file.try_clone()?)),
+            Arc::new(Mutex::new(file)),
+        ))
+    }
+
+    fn assert_send<T: Send>(_: &T) {}
+
+    #[test]
+    fn test_reply_future() -> Result<(), Error> {
+        let stream = stream()?;
+        let replies = Replies::new(
+            stream.clone(),
+            Arc::new(Mutex::new(Vec::new())),
+            Signal::new(),
+        );
+
+        let future = ReplyFuture::new(replies.clone(), 1);
+
+        assert_send(&future);
+
+        let handle = thread::spawn({
+            let replies = replies.clone();
+
+            move || {
+                thread::sleep(std::time::Duration::from_millis(10));
+
+                replies.push(
+                    1,
+                    Reply::GetAtomName {
+                        name: String::from("yaxi"),
+                    },
+                )
+            }
+        });
+
+        assert!(matches!(block_on(future)?, Reply::GetAtomName { name } if name == "yaxi"));
+
+        handle.join().map_err(|_| Error::FailedToLock)??;
+
+        // a reply to a future that was dropped is discarded instead of being kept forever
+        drop(ReplyFuture::new(replies.clone(), 2));
+
+        replies.push(
+            2,
+            Reply::GetAtomName {
+                name: String::new(),
+            },
+        )?;
+
+        stream.shutdown()?;
+        replies.close()?;
+
+        assert!(matches!(
+            block_on(ReplyFuture::new(replies, 2)),
+            Err(Error::ConnectionClosed)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_event_stream() -> Result<(), Error> {
+        let stream = stream()?;
+        let events = Queue::new(
+            Arc::new(Mutex::new(Vec::new())),
+            stream.closed(),
+            Signal::new(),
+        );
+
+        let mut event_stream = EventStream::new(stream.clone(), events.clone());
+
+        assert_send(&event_stream);
+
+        let handle = thread::spawn({
+            let events = events.clone();
+
+            move || {
+                thread::sleep(std::time::Duration::from_millis(10));
+
+                events.push(Event::DestroyNotify {
+                    event: 1,
+                    window: 2,
+                })
+            }
+        });
+
+        assert!(matches!(
+            block_on(event_stream.next_event()),
+            Some(Ok(Event::DestroyNotify { window: 2, .. }))
+        ));
+
+        handle.join().map_err(|_| Error::FailedToLock)??;
+
+        stream.shutdown()?;
+        events.close()?;
+
+        // the stream ends once the connection is closed
+        assert!(block_on(event_stream.next_event()).is_none());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wakers_by_sequence() -> Result<(), Error> {
+        let stream = stream()?;
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let signal = Signal::new();
+
+        let replies = Replies::new(stream.clone(), errors.clone(), signal.clone());
+        let events = Queue::new(errors, stream.closed(), signal);
+
+        let counters = (0..3)
+            .map(|_| Arc::new(CountingWaker::default()))
+            .collect::<Vec<_>>();
+
+        let count = |index: usize| counters[index].0.load(Ordering::SeqCst);
+
+        let mut first = ReplyFuture::new(replies.clone(), 1);
+        let mut second = ReplyFuture::new(replies.clone(), 2);
+        let mut event_stream = EventStream::new(stream.clone(), events.clone());
+
+        let wakers = counters
+            .iter()
+            .map(|counter| Waker::from(counter.clone()))
+            .collect::<Vec<_>>();
+
+        assert!(Pin::new(&mut first)
+            .poll(&mut Context::from_waker(&wakers[0]))
+            .is_pending());
+        assert!(Pin::new(&mut second)
+            .poll(&mut Context::from_waker(&wakers[1]))
+            .is_pending());
+        assert!(Pin::new(&mut event_stream)
+            .poll_next(&mut Context::from_waker(&wakers[2]))
+            .is_pending());
+
+        // a reply only wakes up the future waiting on its sequence number
+        replies.push(
+            2,
+            Reply::GetAtomName {
+                name: String::new(),
+            },
+        )?;
+
+        assert_eq!((count(0), count(1), count(2)), (0, 1, 0));
+
+        // an event only wakes up the event stream
+        events.push(Event::DestroyNotify {
+            event: 1,
+            window: 2,
+        })?;
+
+        assert_eq!((count(0), count(1), count(2)), (0, 1, 1));
+
+        // closing the connection wakes up every future that is still waiting
+        stream.shutdown()?;
+        replies.close()?;
+
+        assert_eq!(count(0), 1);
+
+        Ok(())
+    }
+}
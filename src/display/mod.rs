@@ -2,12 +2,16 @@
 //!
 //!
 
-pub(crate) mod auth;
-pub(crate) mod error;
+pub mod auth;
+pub mod error;
 pub(crate) mod parse;
 pub mod request;
-pub(crate) mod xid;
+pub mod xid;
 
+#[cfg(feature = "async")]
+pub mod future;
+
+use crate::extension::big_requests::BigRequests;
 use crate::extension::Extension;
 
 #[cfg(feature = "xinerama")]
@@ -20,19 +24,24 @@ use crate::keyboard::*;
 use crate::proto::*;
 use crate::window::*;
 
+use auth::Address;
 use error::Error;
 use parse::Protocol;
 use request::*;
+use xid::XidAllocator;
 
 use std::collections::HashMap;
 use std::fs::File;
+use std::hash::Hash;
+use std::io::ErrorKind;
 use std::io::{Read, Write};
-use std::net::{IpAddr, SocketAddr, TcpStream};
+use std::net::{Shutdown, TcpStream, ToSocketAddrs};
+use std::os::fd::{AsRawFd, RawFd};
 use std::os::unix::net::UnixStream;
-use std::sync::{Arc, Mutex};
-use std::thread;
-
-use std::str::FromStr;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::sync::{Arc, Mutex, TryLockError};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, Instant};
 
 // https://www.x.org/docs/XProtocol/proto.pdf
 
@@ -46,15 +55,84 @@ const X_TCP_PORT: u16 = 6000;
 const X_PROTOCOL: u16 = 11;
 const X_PROTOCOL_REVISION: u16 = 0;
 
-pub trait Streamable: Send + Sync + Read + Write {}
+const OUTPUT_BUFFER_SIZE: usize = 16384;
+const READ_BUFFER_SIZE: usize = 16384;
+
+pub trait Streamable: Send + Sync + Read + Write {
+    /// set a timeout for reads, streams that do not support timeouts ignore it
+    fn set_read_timeout(&self, _timeout: Option<Duration>) -> std::io::Result<()> {
+        Ok(())
+    }
 
-impl Streamable for File {}
-impl Streamable for TcpStream {}
-impl Streamable for UnixStream {}
+    /// shut down both directions of the stream so that blocked reads return, streams that can
+    /// not be shut down return an unsupported error
+    fn shutdown(&self) -> std::io::Result<()> {
+        Err(ErrorKind::Unsupported.into())
+    }
+
+    /// switch between blocking and non-blocking reads and writes, streams that can not do
+    /// non-blocking reads return an unsupported error
+    fn set_nonblocking(&self, _nonblocking: bool) -> std::io::Result<()> {
+        Err(ErrorKind::Unsupported.into())
+    }
+
+    /// the file descriptor of the stream if it has one
+    fn raw_fd(&self) -> Option<RawFd> {
+        None
+    }
+}
 
+impl Streamable for File {
+    fn raw_fd(&self) -> Option<RawFd> {
+        Some(self.as_raw_fd())
+    }
+}
+
+impl Streamable for TcpStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        TcpStream::set_read_timeout(self, timeout)
+    }
+
+    fn shutdown(&self) -> std::io::Result<()> {
+        TcpStream::shutdown(self, Shutdown::Both)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> std::io::Result<()> {
+        TcpStream::set_nonblocking(self, nonblocking)
+    }
+
+    fn raw_fd(&self) -> Option<RawFd> {
+        Some(self.as_raw_fd())
+    }
+}
+
+impl Streamable for UnixStream {
+    fn set_read_timeout(&self, timeout: Option<Duration>) -> std::io::Result<()> {
+        UnixStream::set_read_timeout(self, timeout)
+    }
+
+    fn shutdown(&self) -> std::io::Result<()> {
+        UnixStream::shutdown(self, Shutdown::Both)
+    }
+
+    fn set_nonblocking(&self, nonblocking: bool) -> std::io::Result<()> {
+        UnixStream::set_nonblocking(self, nonblocking)
+    }
+
+    fn raw_fd(&self) -> Option<RawFd> {
+        Some(self.as_raw_fd())
+    }
+}
+
+/// requests are collected in the output buffer and written in batches, the buffer is written
+/// when it is full, before waiting on a reply or event and when the display is flushed
 pub struct Stream {
     reader: Arc<Mutex<dyn Streamable>>,
     writer: Arc<Mutex<dyn Streamable>>,
+    buffer: Arc<Mutex<Vec<u8>>>,
+    max_request_length: Arc<AtomicUsize>,
+    big_requests: Arc<Mutex<Option<ExtensionQuery>>>,
+    closed: Arc<AtomicBool>,
 }
 
 impl Clone for Stream {
@@ -62,76 +140,482 @@ impl Clone for Stream {
         Stream {
             reader: self.reader.clone(),
             writer: self.writer.clone(),
+            buffer: self.buffer.clone(),
+            max_request_length: self.max_request_length.clone(),
+            big_requests: self.big_requests.clone(),
+            closed: self.closed.clone(),
         }
     }
 }
 
 impl Stream {
     pub fn new(reader: Arc<Mutex<dyn Streamable>>, writer: Arc<Mutex<dyn Streamable>>) -> Stream {
-        Stream { reader, writer }
+        Stream {
+            reader,
+            writer,
+            buffer: Arc::new(Mutex::new(Vec::with_capacity(OUTPUT_BUFFER_SIZE))),
+            max_request_length: Arc::new(AtomicUsize::new(u16::MAX as usize)),
+            big_requests: Arc::new(Mutex::new(None)),
+            closed: Arc::new(AtomicBool::new(false)),
+        }
     }
 
-    pub fn send(&self, request: &[u8]) -> Result<(), Error> {
+    /// set the maximum length of a request in units of 4 bytes
+    pub fn set_max_request_length(&self, length: usize) {
+        self.max_request_length.store(length, Ordering::Relaxed);
+    }
+
+    /// the maximum length of a request in units of 4 bytes
+    pub fn max_request_length(&self) -> usize {
+        self.max_request_length.load(Ordering::Relaxed)
+    }
+
+    /// set how the big requests extension is queried, it is enabled the first time a request does
+    /// not fit in the 16 bit length field
+    pub(crate) fn set_big_requests(&self, query: Option<ExtensionQuery>) -> Result<(), Error> {
+        *lock!(self.big_requests)? = query;
+
+        Ok(())
+    }
+
+    /// enable the big requests extension if it has not been tried yet, the maximum request length
+    /// stays the same if the server does not support it, the lock is held until the extension is
+    /// enabled so that concurrent requests see the new maximum
+    fn enable_big_requests(&self) -> Result<(), Error> {
+        let mut lock = lock!(self.big_requests)?;
+
+        if let Some(query) = lock.take() {
+            let extension = query.query(Extension::BigRequests)?;
+
+            if extension.present != 0 {
+                let big_requests = BigRequests::new(
+                    query.stream,
+                    query.replies,
+                    query.sequence,
+                    extension.major_opcode,
+                );
+
+                self.set_max_request_length(big_requests.enable()? as usize);
+            }
+        }
+
+        Ok(())
+    }
+
+    /// returns true once the connection is closed, either by the client or the server
+    pub fn is_closed(&self) -> bool {
+        self.closed.load(Ordering::SeqCst)
+    }
+
+    pub(crate) fn closed(&self) -> Arc<AtomicBool> {
+        self.closed.clone()
+    }
+
+    /// mark the stream as closed and shut it down so the listener stops reading, the writer is
+    /// used as the reader is locked by the listener while it waits for the server
+    fn shutdown(&self) -> Result<bool, Error> {
+        self.closed.store(true, Ordering::SeqCst);
+
+        match lock!(self.writer)?.shutdown() {
+            Ok(()) => Ok(true),
+            Err(err) if err.kind() == ErrorKind::NotConnected => Ok(true),
+            Err(_) => Ok(false),
+        }
+    }
+
+    /// set a timeout for reading from the stream
+    pub fn set_read_timeout(&self, timeout: Option<Duration>) -> Result<(), Error> {
+        lock!(self.reader)?.set_read_timeout(timeout)?;
+
+        Ok(())
+    }
+
+    /// run a function while reads do not block, the reader and writer usually share the same
+    /// socket so the writer is locked to keep writes blocking in the meantime, returns none if
+    /// the stream does not support non-blocking reads
+    fn nonblocking<T>(&self, f: impl FnOnce() -> T) -> Result<Option<T>, Error> {
+        let _writer = lock!(self.writer)?;
+
+        match lock!(self.reader)?.set_nonblocking(true) {
+            Ok(()) => {}
+            Err(err) if err.kind() == ErrorKind::Unsupported => return Ok(None),
+            Err(err) => return Err(err.into()),
+        }
+
+        let result = f();
+
+        lock!(self.reader)?.set_nonblocking(false)?;
+
+        Ok(Some(result))
+    }
+
+    /// the file descriptor of the stream if it has one
+    pub fn raw_fd(&self) -> Result<Option<RawFd>, Error> {
+        Ok(lock!(self.writer)?.raw_fd())
+    }
+
+    fn write(&self, buffer: &mut Vec<u8>, request: &[u8]) -> Result<(), Error> {
+        buffer.extend_from_slice(request);
+
+        if buffer.len() >= OUTPUT_BUFFER_SIZE {
+            self.write_buffer(buffer)?;
+        }
+
+        Ok(())
+    }
+
+    fn write_buffer(&self, buffer: &mut Vec<u8>) -> Result<(), Error> {
+        if self.is_closed() {
+            return Err(Error::ConnectionClosed);
+        }
+
         let mut lock = lock!(self.writer)?;
 
-        lock.write_all(request)
-            .map_err(|err| Error::Other { error: err.into() })
+        lock.write_all(buffer).and_then(|_| lock.flush())?;
+
+        buffer.clear();
+
+        Ok(())
+    }
+
+    /// encode the length field of a request, requests longer than what fits in the 16 bit length
+    /// field use the extended length encoding of the big requests extension, requests longer than
+    /// the maximum of the server are rejected before anything is written
+    fn request_length(&self, request: &[u8]) -> Result<Vec<u8>, Error> {
+        let length = (request.len() + request::pad(request.len())) / 4;
+
+        if request.len() < 4 {
+            return Err(Error::Protocol {
+                reason: String::from("request is shorter than its header"),
+            });
+        }
+
+        if length > u16::MAX as usize {
+            self.enable_big_requests()?;
+        }
+
+        let maximum = self.max_request_length.load(Ordering::Relaxed);
+
+        if length <= u16::MAX as usize && length <= maximum {
+            Ok((length as u16).to_le_bytes().to_vec())
+        } else if length > u16::MAX as usize && length < maximum {
+            Ok([[0u8; 2].as_slice(), &(length as u32 + 1).to_le_bytes()].concat())
+        } else {
+            Err(Error::RequestTooLarge {
+                length: request.len(),
+                maximum: maximum * 4,
+            })
+        }
+    }
+
+    /// write a request with the length field replaced by the encoded length
+    fn write_request(
+        &self,
+        buffer: &mut Vec<u8>,
+        request: &[u8],
+        length: &[u8],
+    ) -> Result<(), Error> {
+        self.write(buffer, &request[..2])?;
+        self.write(buffer, length)?;
+        self.write(buffer, &request[4..])?;
+        self.write(buffer, &vec![0u8; request::pad(request.len())])
+    }
+
+    pub fn send(&self, request: &[u8]) -> Result<(), Error> {
+        let mut buffer = lock!(self.buffer)?;
+
+        self.write(&mut buffer, request)
     }
 
     pub fn send_arr(&self, requests: &[Vec<u8>]) -> Result<(), Error> {
-        let mut lock = lock!(self.writer)?;
+        let mut buffer = lock!(self.buffer)?;
 
         for request in requests {
-            lock.write_all(request)
-                .map_err(|err| Error::Other { error: err.into() })?;
+            self.write(&mut buffer, request)?;
         }
 
         Ok(())
     }
 
     pub fn send_pad(&self, request: &[u8]) -> Result<(), Error> {
-        let mut lock = lock!(self.writer)?;
+        let mut buffer = lock!(self.buffer)?;
 
-        lock.write_all(request)
-            .map_err(|err| Error::Other { error: err.into() })?;
+        self.write(&mut buffer, request)?;
 
-        lock.write_all(&vec![0u8; request::pad(request.len())])
-            .map_err(|err| Error::Other { error: err.into() })?;
+        self.write(&mut buffer, &vec![0u8; request::pad(request.len())])
+    }
 
-        Ok(())
+    pub fn send_encode<E: Serialize>(&self, object: E) -> Result<(), Error> {
+        self.send(&request::encode(&object))
+    }
+
+    /// write every buffered request to the server
+    pub fn flush(&self) -> Result<(), Error> {
+        let mut buffer = lock!(self.buffer)?;
+
+        self.write_buffer(&mut buffer)
+    }
+
+    /// send a request without a reply, the sequence number is advanced while the buffer is locked
+    /// so that concurrent requests are numbered in the same order as the server sees them, the
+    /// length field of the request is filled in from the size of the request
+    pub fn send_void(&self, sequence: &SequenceManager, request: &[u8]) -> Result<(), Error> {
+        let length = self.request_length(request)?;
+
+        let mut buffer = lock!(self.buffer)?;
+
+        // the server only reports the lower 16 bits of the sequence number, a request with a
+        // reply is sent before they wrap around so that every response can be matched
+        if sequence.needs_sync() {
+            sequence.append(ReplyKind::Discard)?;
+
+            self.write(
+                &mut buffer,
+                &request::encode(&GetInputFocus {
+                    opcode: Opcode::GET_INPUT_FOCUS,
+                    pad0: 0,
+                    length: 1,
+                }),
+            )?;
+        }
+
+        sequence.skip();
+
+        self.write_request(&mut buffer, request, &length)
     }
 
-    pub fn send_encode<E>(&self, object: E) -> Result<(), Error> {
-        self.send(request::encode(&object))
+    pub fn send_void_encode<E: Serialize>(
+        &self,
+        sequence: &SequenceManager,
+        object: E,
+    ) -> Result<(), Error> {
+        self.send_void(sequence, &request::encode(&object))
+    }
+
+    /// send a request that generates a reply and return its sequence number, use
+    /// `Replies::wait` with the sequence number to get the reply
+    pub fn send_reply(
+        &self,
+        sequence: &SequenceManager,
+        kind: ReplyKind,
+        request: &[u8],
+    ) -> Result<u64, Error> {
+        let length = self.request_length(request)?;
+
+        let mut buffer = lock!(self.buffer)?;
+
+        let id = sequence.append(kind)?;
+
+        self.write_request(&mut buffer, request, &length)?;
+
+        Ok(id)
+    }
+
+    pub fn send_reply_encode<E: Serialize>(
+        &self,
+        sequence: &SequenceManager,
+        kind: ReplyKind,
+        object: E,
+    ) -> Result<u64, Error> {
+        self.send_reply(sequence, kind, &request::encode(&object))
     }
 
     pub fn recv(&self, size: usize) -> Result<Vec<u8>, Error> {
         let mut lock = lock!(self.reader)?;
         let mut buffer = vec![0u8; size];
 
-        match lock.read_exact(&mut buffer) {
-            Ok(()) => Ok(buffer),
-            Err(err) => Err(Error::Other { error: err.into() }),
-        }
+        lock.read_exact(&mut buffer)?;
+
+        Ok(buffer)
     }
 
     pub fn recv_str(&self, size: usize) -> Result<String, Error> {
         let bytes = self.recv(size)?;
 
-        self.recv(size % 4)?;
+        self.recv(request::pad(size))?;
 
         String::from_utf8(bytes).map_err(|_| Error::Utf8)
     }
 
-    pub fn recv_decode<R>(&self) -> Result<R, Error> {
-        let bytes = self.recv(std::mem::size_of::<R>())?;
+    pub fn recv_decode<R: Deserialize>(&self) -> Result<R, Error> {
+        let bytes = self.recv(R::SIZE)?;
+
+        request::decode(&bytes)
+    }
+
+    /// read as many bytes as are available into the buffer, blocks until at least one byte can
+    /// be read
+    pub fn read(&self, buffer: &mut [u8]) -> Result<usize, Error> {
+        Ok(lock!(self.reader)?.read(buffer)?)
+    }
+}
+
+/// the reader keeps the bytes read from the stream in a reusable buffer, a single read usually
+/// covers many responses which are then parsed from memory
+pub struct Reader {
+    stream: Stream,
+    buffer: Vec<u8>,
+    start: usize,
+    end: usize,
+    response: usize,
+}
+
+impl Reader {
+    pub fn new(stream: Stream) -> Reader {
+        Reader {
+            stream,
+            buffer: vec![0u8; READ_BUFFER_SIZE],
+            start: 0,
+            end: 0,
+            response: 0,
+        }
+    }
+
+    /// make sure that at least size bytes after the start are in the buffer
+    fn fill(&mut self, size: usize) -> Result<(), Error> {
+        if self.start + size > self.buffer.len() {
+            self.buffer.copy_within(self.start..self.end, 0);
+
+            self.end -= self.start;
+            self.start = 0;
+
+            if size > self.buffer.len() {
+                self.buffer.resize(size, 0);
+            }
+        }
+
+        while self.end < self.start + size {
+            match self.stream.read(&mut self.buffer[self.end..])? {
+                0 => return Err(std::io::Error::from(ErrorKind::UnexpectedEof).into()),
+                read => self.end += read,
+            }
+        }
+
+        Ok(())
+    }
+
+    /// skip whatever is left of the current response and read the next one into the buffer,
+    /// every response is 32 bytes except for replies and generic events which are followed by
+    /// additional data
+    pub fn next_response(&mut self) -> Result<(), Error> {
+        self.start = self.response;
+
+        self.fill(32)?;
+
+        let size = match self.buffer[self.start] & 0b0111111 {
+            Response::REPLY | Response::GENERIC_EVENT => {
+                let length: u32 = request::decode(&self.buffer[self.start + 4..])?;
+
+                32 + length as usize * 4
+            }
+            _ => 32,
+        };
+
+        self.fill(size)?;
+
+        self.response = self.start + size;
+
+        Ok(())
+    }
+
+    fn take(&mut self, size: usize) -> Result<&[u8], Error> {
+        if self.start + size > self.response {
+            return Err(Error::Protocol {
+                reason: format!(
+                    "response is {} bytes shorter than expected",
+                    self.start + size - self.response
+                ),
+            });
+        }
+
+        self.start += size;
+
+        Ok(&self.buffer[self.start - size..self.start])
+    }
 
-        Ok(request::decode(&bytes))
+    pub fn recv(&mut self, size: usize) -> Result<Vec<u8>, Error> {
+        self.take(size).map(|bytes| bytes.to_vec())
+    }
+
+    pub fn recv_decode<R: Deserialize>(&mut self) -> Result<R, Error> {
+        self.take(R::SIZE).and_then(request::decode)
     }
 }
 
+/// the names of the predefined atoms, the atom of a name is its index plus one
+const PREDEFINED_ATOMS: [&str; 68] = [
+    "PRIMARY",
+    "SECONDARY",
+    "ARC",
+    "ATOM",
+    "BITMAP",
+    "CARDINAL",
+    "COLORMAP",
+    "CURSOR",
+    "CUT_BUFFER0",
+    "CUT_BUFFER1",
+    "CUT_BUFFER2",
+    "CUT_BUFFER3",
+    "CUT_BUFFER4",
+    "CUT_BUFFER5",
+    "CUT_BUFFER6",
+    "CUT_BUFFER7",
+    "DRAWABLE",
+    "FONT",
+    "INTEGER",
+    "PIXMAP",
+    "POINT",
+    "RECTANGLE",
+    "RESOURCE_MANAGER",
+    "RGB_COLOR_MAP",
+    "RGB_BEST_MAP",
+    "RGB_BLUE_MAP",
+    "RGB_DEFAULT_MAP",
+    "RGB_GRAY_MAP",
+    "RGB_GREEN_MAP",
+    "RGB_RED_MAP",
+    "STRING",
+    "VISUALID",
+    "WINDOW",
+    "WM_COMMAND",
+    "WM_HINTS",
+    "WM_CLIENT_MACHINE",
+    "WM_ICON_NAME",
+    "WM_ICON_SIZE",
+    "WM_NAME",
+    "WM_NORMAL_HINTS",
+    "WM_SIZE_HINTS",
+    "WM_ZOOM_HINTS",
+    "MIN_SPACE",
+    "NORM_SPACE",
+    "MAX_SPACE",
+    "END_SPACE",
+    "SUPERSCRIPT_X",
+    "SUPERSCRIPT_Y",
+    "SUBSCRIPT_X",
+    "SUBSCRIPT_Y",
+    "UNDERLINE_POSITION",
+    "UNDERLINE_THICKNESS",
+    "STRIKEOUT_ASCENT",
+    "STRIKEOUT_DESCENT",
+    "ITALIC_ANGLE",
+    "X_HEIGHT",
+    "QUAD_WIDTH",
+    "WEIGHT",
+    "POINT_SIZE",
+    "RESOLUTION",
+    "COPYRIGHT",
+    "NOTICE",
+    "FONT_NAME",
+    "FAMILY_NAME",
+    "FULL_NAME",
+    "CAP_HEIGHT",
+    "WM_CLASS",
+    "WM_TRANSIENT_FOR",
+];
+
 /// an atom in the x11 protocol is an integer representing a string
-/// atoms in the range 1..=68 are predefined (only 1..=20 implemented so far)
+/// atoms in the range 1..=68 are predefined
 #[derive(Debug, Default, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct Atom {
     id: u32,
@@ -161,7 +645,54 @@ impl Atom {
     pub const FONT: Atom = Atom::new(18);
     pub const INTEGER: Atom = Atom::new(19);
     pub const PIXMAP: Atom = Atom::new(20);
+    pub const POINT: Atom = Atom::new(21);
+    pub const RECTANGLE: Atom = Atom::new(22);
+    pub const RESOURCE_MANAGER: Atom = Atom::new(23);
+    pub const RGB_COLOR_MAP: Atom = Atom::new(24);
+    pub const RGB_BEST_MAP: Atom = Atom::new(25);
+    pub const RGB_BLUE_MAP: Atom = Atom::new(26);
+    pub const RGB_DEFAULT_MAP: Atom = Atom::new(27);
+    pub const RGB_GRAY_MAP: Atom = Atom::new(28);
+    pub const RGB_GREEN_MAP: Atom = Atom::new(29);
+    pub const RGB_RED_MAP: Atom = Atom::new(30);
+    pub const STRING: Atom = Atom::new(31);
+    pub const VISUALID: Atom = Atom::new(32);
     pub const WINDOW: Atom = Atom::new(33);
+    pub const WM_COMMAND: Atom = Atom::new(34);
+    pub const WM_HINTS: Atom = Atom::new(35);
+    pub const WM_CLIENT_MACHINE: Atom = Atom::new(36);
+    pub const WM_ICON_NAME: Atom = Atom::new(37);
+    pub const WM_ICON_SIZE: Atom = Atom::new(38);
+    pub const WM_NAME: Atom = Atom::new(39);
+    pub const WM_NORMAL_HINTS: Atom = Atom::new(40);
+    pub const WM_SIZE_HINTS: Atom = Atom::new(41);
+    pub const WM_ZOOM_HINTS: Atom = Atom::new(42);
+    pub const MIN_SPACE: Atom = Atom::new(43);
+    pub const NORM_SPACE: Atom = Atom::new(44);
+    pub const MAX_SPACE: Atom = Atom::new(45);
+    pub const END_SPACE: Atom = Atom::new(46);
+    pub const SUPERSCRIPT_X: Atom = Atom::new(47);
+    pub const SUPERSCRIPT_Y: Atom = Atom::new(48);
+    pub const SUBSCRIPT_X: Atom = Atom::new(49);
+    pub const SUBSCRIPT_Y: Atom = Atom::new(50);
+    pub const UNDERLINE_POSITION: Atom = Atom::new(51);
+    pub const UNDERLINE_THICKNESS: Atom = Atom::new(52);
+    pub const STRIKEOUT_ASCENT: Atom = Atom::new(53);
+    pub const STRIKEOUT_DESCENT: Atom = Atom::new(54);
+    pub const ITALIC_ANGLE: Atom = Atom::new(55);
+    pub const X_HEIGHT: Atom = Atom::new(56);
+    pub const QUAD_WIDTH: Atom = Atom::new(57);
+    pub const WEIGHT: Atom = Atom::new(58);
+    pub const POINT_SIZE: Atom = Atom::new(59);
+    pub const RESOLUTION: Atom = Atom::new(60);
+    pub const COPYRIGHT: Atom = Atom::new(61);
+    pub const NOTICE: Atom = Atom::new(62);
+    pub const FONT_NAME: Atom = Atom::new(63);
+    pub const FAMILY_NAME: Atom = Atom::new(64);
+    pub const FULL_NAME: Atom = Atom::new(65);
+    pub const CAP_HEIGHT: Atom = Atom::new(66);
+    pub const WM_CLASS: Atom = Atom::new(67);
+    pub const WM_TRANSIENT_FOR: Atom = Atom::new(68);
 
     /// create a new atom from its id
     pub const fn new(id: u32) -> Atom {
@@ -178,6 +709,21 @@ impl Atom {
         self.id == 0
     }
 
+    /// get a predefined atom from its name
+    pub fn predefined(name: &str) -> Option<Atom> {
+        PREDEFINED_ATOMS
+            .iter()
+            .position(|predefined| *predefined == name)
+            .map(|index| Atom::new(index as u32 + 1))
+    }
+
+    /// get the name of the atom if it is predefined
+    pub fn predefined_name(&self) -> Option<&'static str> {
+        PREDEFINED_ATOMS
+            .get((self.id as usize).wrapping_sub(1))
+            .copied()
+    }
+
     /// get ne bytes of the atom
     pub fn to_ne_bytes(&self) -> [u8; 4] {
         self.id.to_ne_bytes()
@@ -187,6 +733,16 @@ impl Atom {
     pub fn from_ne_bytes(bytes: [u8; 4]) -> Atom {
         Atom::new(u32::from_ne_bytes(bytes))
     }
+
+    /// get le bytes of the atom, this is the byte order of property data
+    pub fn to_le_bytes(&self) -> [u8; 4] {
+        self.id.to_le_bytes()
+    }
+
+    /// get atom from le bytes
+    pub fn from_le_bytes(bytes: [u8; 4]) -> Atom {
+        Atom::new(u32::from_le_bytes(bytes))
+    }
 }
 
 impl TryFrom<&[u8]> for Atom {
@@ -195,8 +751,8 @@ impl TryFrom<&[u8]> for Atom {
     fn try_from(bytes: &[u8]) -> Result<Atom, Error> {
         match bytes.len() {
             4 => {
-                let ne_bytes = bytes.try_into().map_err(|_| Error::InvalidAtom)?;
-                let id = u32::from_ne_bytes(ne_bytes);
+                let le_bytes = bytes.try_into().map_err(|_| Error::InvalidAtom)?;
+                let id = u32::from_le_bytes(le_bytes);
                 Ok(Atom::new(id))
             }
             _ => Err(Error::InvalidAtom),
@@ -210,18 +766,74 @@ impl std::fmt::Display for Atom {
     }
 }
 
+/// declare a struct of named atoms that are interned together in a single round trip
+///
+/// ```no_run
+/// yaxi::atoms! {
+///     pub struct Atoms {
+///         pub utf8_string: "UTF8_STRING",
+///         pub net_wm_name: "_NET_WM_NAME",
+///     }
+/// }
+///
+/// let display = yaxi::display::open(None).unwrap();
+///
+/// let atoms = Atoms::new(&display).unwrap();
+/// ```
+#[macro_export]
+macro_rules! atoms {
+    (
+        $(#[$meta:meta])*
+        $vis:vis struct $name:ident {
+            $($field_vis:vis $field:ident: $atom:literal),* $(,)?
+        }
+    ) => {
+        $(#[$meta])*
+        #[derive(Debug, Clone, Copy)]
+        $vis struct $name {
+            $($field_vis $field: $crate::display::Atom),*
+        }
+
+        #[allow(dead_code)]
+        impl $name {
+            /// intern every atom of the struct
+            pub fn new(
+                display: &$crate::display::Display,
+            ) -> Result<$name, $crate::display::error::Error> {
+                let mut atoms = display.intern_atoms(&[$($atom),*])?.into_iter();
+
+                Ok($name {
+                    $(
+                        $field: atoms
+                            .next()
+                            .ok_or($crate::display::error::Error::InvalidAtom)?,
+                    )*
+                })
+            }
+
+            /// get every atom of the struct together with its name
+            pub fn names(&self) -> Vec<($crate::display::Atom, &'static str)> {
+                vec![$((self.$field, $atom)),*]
+            }
+        }
+    };
+}
+
+/// the cache maps names to values and values back to their names
 #[derive(Clone)]
-struct Cache<T: Clone + Copy> {
+struct Cache<T: Clone + Copy + Eq + Hash> {
     cache: Arc<Mutex<HashMap<String, T>>>,
+    names: Arc<Mutex<HashMap<T, String>>>,
 }
 
 impl<T> Cache<T>
 where
-    T: Clone + Copy,
+    T: Clone + Copy + Eq + Hash,
 {
     pub fn new() -> Cache<T> {
         Cache {
             cache: Arc::new(Mutex::new(HashMap::new())),
+            names: Arc::new(Mutex::new(HashMap::new())),
         }
     }
 
@@ -229,14 +841,22 @@ where
         lock!(self.cache).map(|cache| cache.get(key).copied())
     }
 
+    pub fn name(&self, value: T) -> Result<Option<String>, Error> {
+        lock!(self.names).map(|names| names.get(&value).cloned())
+    }
+
     pub fn insert(&self, key: &str, value: T) -> Result<(), Error> {
         lock!(self.cache)?.insert(key.to_string(), value);
+        lock!(self.names)?.insert(value, key.to_string());
 
         Ok(())
     }
 
     pub fn clear(&self) -> Result<(), Error> {
-        lock!(self.cache).map(|mut cache| cache.clear())
+        lock!(self.cache)?.clear();
+        lock!(self.names)?.clear();
+
+        Ok(())
     }
 }
 
@@ -247,11 +867,11 @@ pub struct Visual {
 }
 
 impl Visual {
-    pub fn new(response: VisualResponse) -> Visual {
-        Visual {
+    pub fn new(response: VisualResponse) -> Result<Visual, Error> {
+        Ok(Visual {
             id: response.visual_id,
-            class: VisualClass::from(response.class),
-        }
+            class: VisualClass::try_from(response.class)?,
+        })
     }
 }
 
@@ -271,9 +891,22 @@ impl Depth {
         }
     }
 
-    pub fn extend(&mut self, responses: &[VisualResponse]) {
-        self.visuals
-            .extend(responses.iter().map(|response| Visual::new(*response)));
+    pub fn extend(&mut self, responses: &[VisualResponse]) -> Result<(), Error> {
+        for response in responses {
+            self.visuals.push(Visual::new(*response)?);
+        }
+
+        Ok(())
+    }
+
+    /// the depth in bits per pixel
+    pub fn depth(&self) -> u8 {
+        self.depth
+    }
+
+    /// the visuals available with this depth
+    pub fn visuals(&self) -> &[Visual] {
+        &self.visuals
     }
 }
 
@@ -289,61 +922,357 @@ impl KeycodeRange {
     }
 }
 
-#[derive(Clone)]
-pub struct Screen {
-    pub response: ScreenResponse,
-    pub depths: Vec<Depth>,
+#[derive(Clone)]
+pub struct Screen {
+    pub response: ScreenResponse,
+    pub depths: Vec<Depth>,
+}
+
+impl Screen {
+    pub fn new(response: ScreenResponse) -> Screen {
+        Screen {
+            response,
+            depths: Vec::new(),
+        }
+    }
+
+    /// the id of the root window of the screen
+    pub fn root(&self) -> u32 {
+        self.response.root
+    }
+
+    /// the depth of the root window
+    pub fn root_depth(&self) -> u8 {
+        self.response.root_depth
+    }
+
+    /// the visual of the root window
+    pub fn root_visual(&self) -> Result<Visual, Error> {
+        self.visual_from_id(self.response.root_visual)
+    }
+
+    /// the depths supported by the screen
+    pub fn depths(&self) -> &[Depth] {
+        &self.depths
+    }
+
+    /// get a visual of the screen from its id
+    pub fn visual_from_id(&self, id: u32) -> Result<Visual, Error> {
+        self.find_visual(id).map(|(_, visual)| visual.clone())
+    }
+
+    /// find a visual and the depth it belongs to
+    pub(crate) fn find_visual(&self, id: u32) -> Result<(u8, &Visual), Error> {
+        self.depths
+            .iter()
+            .find_map(|depth| {
+                depth
+                    .visuals
+                    .iter()
+                    .find(|visual| visual.id == id)
+                    .map(|visual| (depth.depth, visual))
+            })
+            .ok_or(Error::InvalidId)
+    }
+}
+
+/// information the server sent when the connection was set up
+#[derive(Debug, Clone, Default)]
+pub struct SetupInfo {
+    pub protocol_major_version: u16,
+    pub protocol_minor_version: u16,
+    pub release_number: u32,
+    pub vendor: String,
+    /// the maximum request length in units of 4 bytes sent during setup, big requests are enabled
+    /// once a request is longer than this
+    pub maximum_request_length: u32,
+    pub image_byte_order: ImageByteOrder,
+    pub bitmap_bit_order: BitmapBitOrder,
+    pub bitmap_scanline_unit: u8,
+    pub bitmap_scanline_pad: u8,
+    pub motion_buffer_size: u32,
+    pub pixmap_formats: Vec<PixmapFormat>,
+    pub min_keycode: u8,
+    pub max_keycode: u8,
+}
+
+#[derive(Clone)]
+pub struct Roots {
+    roots: Vec<Screen>,
+}
+
+impl Roots {
+    pub fn new() -> Roots {
+        Roots { roots: Vec::new() }
+    }
+
+    pub fn first(&self) -> Result<&Screen, Error> {
+        self.roots.first().ok_or(Error::NoScreens)
+    }
+
+    /// get a screen from its number
+    pub fn get(&self, screen: usize) -> Result<&Screen, Error> {
+        self.roots
+            .get(screen)
+            .ok_or(Error::InvalidScreen { screen })
+    }
+
+    /// get the screen that a root window belongs to
+    pub fn from_root(&self, root: u32) -> Result<&Screen, Error> {
+        self.roots
+            .iter()
+            .find(|screen| screen.response.root == root)
+            .ok_or(Error::InvalidId)
+    }
+
+    /// all screens of the display
+    pub fn screens(&self) -> &[Screen] {
+        &self.roots
+    }
+
+    pub fn visual_from_id(&self, id: u32) -> Result<Visual, Error> {
+        for screen in &self.roots {
+            for depth in &screen.depths {
+                match depth.visuals.iter().find(|visual| visual.id == id) {
+                    Some(visual) => return Ok(visual.clone()),
+                    None => {}
+                }
+            }
+        }
+
+        Err(Error::InvalidId)
+    }
+
+    pub fn push(&mut self, screen: Screen) {
+        self.roots.push(screen);
+    }
+}
+
+/// queries extensions for the stream and the xid allocator, which enable big requests and
+/// xc-misc only once they are needed
+#[derive(Clone)]
+pub(crate) struct ExtensionQuery {
+    stream: Stream,
+    replies: Replies,
+    sequence: SequenceManager,
+    extensions: Arc<Mutex<HashMap<u8, String>>>,
+}
+
+impl ExtensionQuery {
+    /// query an extension and if its active get its major opcode
+    pub fn query(&self, extension: Extension) -> Result<QueryExtensionResponse, Error> {
+        let request = QueryExtension {
+            opcode: Opcode::QUERY_EXTENSION,
+            pad0: 0,
+            length: 2 + (extension.len() as u16 + request::pad(extension.len()) as u16) / 4,
+            name_len: extension.len() as u16,
+            pad1: 0,
+        };
+
+        let extension = extension.to_string();
+
+        let sequence = self.stream.send_reply(
+            &self.sequence,
+            ReplyKind::QueryExtension,
+            &[
+                request::encode(&request),
+                extension.as_bytes().to_vec(),
+                vec![0u8; request::pad(extension.as_bytes().len())],
+            ]
+            .concat(),
+        )?;
+
+        match self.replies.wait(sequence)? {
+            Reply::QueryExtension(response) => {
+                // remembered so that errors for extension requests can name the extension
+                if response.present != 0 {
+                    lock!(self.extensions)?.insert(response.major_opcode, extension);
+                }
+
+                Ok(response)
+            }
+            _ => Err(Error::UnexpectedReply),
+        }
+    }
+}
+
+/// the callback run when the server closes the connection
+type DisconnectCallback = Box<dyn FnOnce(Error) + Send>;
+
+/// shared by all clones of a display and the windows and extensions it hands out, the connection
+/// is closed when the last of them is dropped
+pub(crate) struct Connection {
+    stream: Stream,
+    events: Queue<Event>,
+    replies: Replies,
+    signal: Signal,
+    listener: Mutex<Option<JoinHandle<()>>>,
+    on_disconnect: Arc<Mutex<Option<DisconnectCallback>>>,
+    single_threaded: bool,
+}
+
+impl Connection {
+    fn close(&self) -> Result<(), Error> {
+        if !self.stream.is_closed() {
+            // requests that are still buffered would otherwise never reach the server
+            let _ = self.stream.flush();
+        }
+
+        let stopped = self.stream.shutdown()?;
+
+        // the inline listener holds on to the queues and the big requests query holds on to the
+        // stream, this breaks the cycles
+        self.signal.set_driver(None)?;
+        self.stream.set_big_requests(None)?;
+
+        self.events.close()?;
+        self.replies.close()?;
+
+        // the listener can only be joined if shutting down the stream made its read return, and
+        // never from the listener itself which happens when close is called from the callback
+        if let Some(listener) = lock!(self.listener)?.take() {
+            if stopped && listener.thread().id() != thread::current().id() {
+                listener.join().map_err(|_| Error::FailedToLock)?;
+            }
+        }
+
+        Ok(())
+    }
+}
+
+impl Drop for Connection {
+    fn drop(&mut self) {
+        let _ = self.close();
+    }
 }
 
-impl Screen {
-    pub fn new(response: ScreenResponse) -> Screen {
-        Screen {
-            response,
-            depths: Vec::new(),
+/// stop the connection after the listener stopped reading, the callback only runs if the server
+/// closed the connection and not the client
+fn disconnect(
+    stream: &Stream,
+    events: &Queue<Event>,
+    replies: &Replies,
+    on_disconnect: &Mutex<Option<DisconnectCallback>>,
+    error: Error,
+) {
+    // the stream is already marked as closed when the client closed the connection
+    let lost = !stream.is_closed();
+
+    let _ = stream.shutdown();
+    let _ = events.close();
+    let _ = replies.close();
+
+    if lost {
+        if let Some(callback) = lock!(on_disconnect).ok().and_then(|mut lock| lock.take()) {
+            callback(error);
         }
     }
 }
 
-#[derive(Clone)]
-pub struct Roots {
-    roots: Vec<Screen>,
+/// reads responses on the threads that wait for them in single threaded mode, only one thread
+/// reads at a time
+struct InlineListener {
+    listener: Mutex<EventListener>,
+    stream: Stream,
+    events: Queue<Event>,
+    replies: Replies,
+    signal: Signal,
+    on_disconnect: Arc<Mutex<Option<DisconnectCallback>>>,
 }
 
-impl Roots {
-    pub fn new() -> Roots {
-        Roots { roots: Vec::new() }
-    }
+impl InlineListener {
+    fn read(
+        &self,
+        listener: &mut EventListener,
+        deadline: Option<Instant>,
+    ) -> Result<Drive, Error> {
+        let timeout = deadline.map(|deadline| deadline.saturating_duration_since(Instant::now()));
+
+        let result = match timeout {
+            Some(timeout) if timeout.is_zero() => {
+                match self.stream.nonblocking(|| listener.read_response())? {
+                    Some(result) => result,
+                    None => return Ok(Drive::TimedOut),
+                }
+            }
+            timeout => {
+                self.stream.set_read_timeout(timeout)?;
 
-    pub fn first(&self) -> Result<&Screen, Error> {
-        self.roots.first().ok_or(Error::NoScreens)
+                listener.read_response()
+            }
+        };
+
+        match result {
+            Ok(()) => Ok(Drive::Read),
+            Err(Error::Stream { error })
+                if matches!(error.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) =>
+            {
+                Ok(Drive::TimedOut)
+            }
+            Err(error) => Err(error),
+        }
     }
+}
 
-    pub fn visual_from_id(&self, id: u32) -> Result<Visual, Error> {
-        for screen in &self.roots {
-            for depth in &screen.depths {
-                match depth.visuals.iter().find(|visual| visual.id == id) {
-                    Some(visual) => return Ok(visual.clone()),
-                    None => {}
-                }
+impl Driver for InlineListener {
+    fn drive(&self, deadline: Option<Instant>) -> Result<Drive, Error> {
+        let mut listener = match self.listener.try_lock() {
+            Ok(listener) => listener,
+            // the caller checks again once the other thread is done reading
+            Err(TryLockError::WouldBlock) => {
+                drop(lock!(self.listener)?);
+
+                return Ok(Drive::Retry);
             }
+            Err(TryLockError::Poisoned(_)) => return Err(Error::FailedToLock),
+        };
+
+        if self.stream.is_closed() {
+            return Err(Error::ConnectionClosed);
         }
 
-        Err(Error::InvalidId)
-    }
+        // like with the listener thread the waiters see the connection close and the callback
+        // gets the error that closed it
+        self.read(&mut listener, deadline).or_else(|error| {
+            self.signal.set_driver(None)?;
 
-    pub fn push(&mut self, screen: Screen) {
-        self.roots.push(screen);
+            disconnect(
+                &self.stream,
+                &self.events,
+                &self.replies,
+                &self.on_disconnect,
+                error,
+            );
+
+            Err(Error::ConnectionClosed)
+        })
     }
 }
 
+/// a connection to an x server, windows and extension handles taken from the display share its
+/// connection and keep it open, the connection is closed once the display, its clones and those
+/// handles are all dropped or when [`Display::close`] is called
 pub struct Display {
     pub(crate) stream: Stream,
     pub(crate) events: Queue<Event>,
-    pub(crate) replies: Queue<Reply>,
+    pub(crate) replies: Replies,
     pub(crate) roots: Roots,
     pub(crate) setup: SuccessResponse,
+    pub(crate) info: Arc<SetupInfo>,
     pub(crate) sequence: SequenceManager,
     pub(crate) atom_cache: Cache<Atom>,
+    pub(crate) xid: XidAllocator,
+    pub(crate) screen: usize,
+    pub(crate) extensions: Arc<Mutex<HashMap<u8, String>>>,
+    pub(crate) signal: Signal,
+    connection: Arc<Connection>,
+}
+
+impl Drop for Display {
+    fn drop(&mut self) {
+        // requests that are still buffered would otherwise never reach the server
+        let _ = self.stream.flush();
+    }
 }
 
 impl Clone for Display {
@@ -356,92 +1285,237 @@ impl Clone for Display {
             replies: self.replies.clone(),
             roots: self.roots.clone(),
             setup: self.setup.clone(),
+            info: self.info.clone(),
             sequence: self.sequence.clone(),
             atom_cache: self.atom_cache.clone(),
+            xid: self.xid.clone(),
+            screen: self.screen,
+            extensions: self.extensions.clone(),
+            signal: self.signal.clone(),
+            connection: self.connection.clone(),
         }
     }
 }
 
 impl Display {
+    /// connect over an existing stream, this authenticates with the xauthority entry for the
+    /// local machine and the display number from $DISPLAY, use [`ConnectOptions`] for more control
     pub fn connect<'a>(stream: Stream) -> Result<Display, Error> {
-        let errors: Arc<Mutex<Vec<Error>>> = Arc::new(Mutex::new(Vec::new()));
+        ConnectOptions::new().connect_stream(stream)
+    }
+
+    pub(crate) fn connect_with(
+        stream: Stream,
+        auth: Option<(Vec<u8>, Vec<u8>)>,
+        setup_timeout: Option<Duration>,
+        single_threaded: bool,
+    ) -> Result<Display, Error> {
+        let errors: Errors = Arc::new(Mutex::new(Vec::new()));
+
+        let signal = Signal::new();
+
+        let replies = Replies::new(stream.clone(), errors.clone(), signal.clone());
+        let events = Queue::new(errors.clone(), stream.closed(), signal.clone());
+
+        let connection = Arc::new(Connection {
+            stream: stream.clone(),
+            events: events.clone(),
+            replies: replies.clone(),
+            signal: signal.clone(),
+            listener: Mutex::new(None),
+            on_disconnect: Arc::new(Mutex::new(None)),
+            single_threaded,
+        });
 
         let mut display = Display {
             stream,
-            events: Queue::new(errors.clone()),
-            replies: Queue::new(errors.clone()),
+            events,
+            replies,
             roots: Roots::new(),
             setup: SuccessResponse::default(),
+            info: Arc::new(SetupInfo::default()),
             sequence: SequenceManager::new(),
             atom_cache: Cache::new(),
+            xid: XidAllocator::new(),
+            screen: 0,
+            extensions: Arc::new(Mutex::new(HashMap::new())),
+            signal,
+            connection,
         };
 
-        display.setup()?;
+        display.setup(auth, setup_timeout)?;
 
         Ok(display)
     }
 
+    /// close the connection and stop the listener, every thread waiting on an event or a reply
+    /// wakes up with [`Error::ConnectionClosed`], this also happens when the last clone of the
+    /// display and the last window or extension handle taken from it are dropped
+    pub fn close(&self) -> Result<(), Error> {
+        self.connection.close()
+    }
+
+    /// returns true once the connection is closed, either by the client or the server
+    pub fn is_closed(&self) -> bool {
+        self.stream.is_closed()
+    }
+
+    /// set a callback that runs on the listener thread when the server closes the connection,
+    /// the callback receives the error that ended the connection
+    pub fn on_disconnect<F>(&self, callback: F) -> Result<(), Error>
+    where
+        F: FnOnce(Error) + Send + 'static,
+    {
+        *lock!(self.connection.on_disconnect)? = Some(Box::new(callback));
+
+        Ok(())
+    }
+
     /// wait for the next event
     pub fn next_event(&self) -> Result<Event, Error> {
+        self.stream.flush()?;
+
         self.events.wait()
     }
 
     /// returns true if an event is ready
     pub fn poll_event(&self) -> Result<bool, Error> {
+        self.stream.flush()?;
+
         self.events.poll()
     }
 
+    /// get the next event if one is ready without waiting
+    pub fn try_next_event(&self) -> Result<Option<Event>, Error> {
+        self.stream.flush()?;
+
+        self.events.try_pop()
+    }
+
+    /// wait for the next event, returns none if no event arrived before the timeout
+    pub fn next_event_timeout(&self, timeout: Duration) -> Result<Option<Event>, Error> {
+        self.stream.flush()?;
+
+        self.events.wait_timeout(timeout)
+    }
+
+    /// get a file descriptor that is readable while events are queued or once the connection is
+    /// closed, this lets the display be watched by poll or epoll next to other file descriptors
+    ///
+    /// the descriptor belongs to the display and must never be read from or closed, once it is
+    /// readable call [`Display::try_next_event`] until it returns none
+    ///
+    /// in single threaded mode this is the socket of the connection which is readable once the
+    /// server sent something, the responses are only read by [`Display::try_next_event`]
+    pub fn event_fd(&self) -> Result<RawFd, Error> {
+        self.stream.flush()?;
+
+        if self.connection.single_threaded {
+            if let Some(fd) = self.stream.raw_fd()? {
+                return Ok(fd);
+            }
+        }
+
+        self.events.raw_fd()
+    }
+
+    /// wait until an event is ready or a reply or error arrives for any request, without a
+    /// timeout this waits until one of them happens, returns false if the timeout passed first
+    ///
+    /// events and replies wake up the same waiters so a single thread can handle events while
+    /// other threads are waiting on replies
+    ///
+    /// nothing is taken from the queue, errors of requests without a reply belong to
+    /// [`Display::sync`] and the event functions such as [`Display::try_next_event`], a pending
+    /// error only makes this return true so that the next event function returns it
+    pub fn wait_for_activity(&self, timeout: Option<Duration>) -> Result<bool, Error> {
+        self.stream.flush()?;
+
+        self.events
+            .wait_ready(timeout.and_then(|timeout| Instant::now().checked_add(timeout)))
+    }
+
     /// get the window from its id
     pub fn window_from_id(&self, id: u32) -> Result<Window, Error> {
         Window::from_id(
             self.stream.clone(),
             self.replies.clone(),
             self.sequence.clone(),
+            self.xid.clone(),
             self.roots.clone(),
             id,
+            self.connection.clone(),
         )
     }
 
+    /// get the information the server sent when the connection was set up
+    pub fn setup_info(&self) -> &SetupInfo {
+        &self.info
+    }
+
+    /// get all screens of the display
+    pub fn screens(&self) -> &[Screen] {
+        self.roots.screens()
+    }
+
+    /// get a screen from its number
+    pub fn screen(&self, screen: usize) -> Result<&Screen, Error> {
+        self.roots.get(screen)
+    }
+
+    /// get the default screen, this is the screen from $DISPLAY unless it was changed
+    pub fn default_screen(&self) -> Result<&Screen, Error> {
+        self.roots.get(self.screen)
+    }
+
+    /// get the number of the default screen
+    pub fn default_screen_number(&self) -> usize {
+        self.screen
+    }
+
+    /// change the default screen used for the default root window
+    pub fn set_default_screen(&mut self, screen: usize) -> Result<(), Error> {
+        self.roots.get(screen)?;
+
+        self.screen = screen;
+
+        Ok(())
+    }
+
     /// get the default root window of a display
     pub fn default_root_window(&self) -> Result<Window, Error> {
-        let screen = self.roots.first()?;
+        self.root_window(self.screen)
+    }
+
+    /// get the root window of a screen
+    pub fn root_window(&self, screen: usize) -> Result<Window, Error> {
+        let screen = self.roots.get(screen)?;
 
         Ok(Window::new(
             self.stream.clone(),
             self.replies.clone(),
             self.sequence.clone(),
-            self.roots.visual_from_id(screen.response.root_visual)?,
+            self.xid.clone(),
+            screen.root_visual()?,
             screen.response.root_depth,
             screen.response.root,
+            self.connection.clone(),
         ))
     }
 
     /// query an extension and if its active get its major opcode
     pub fn query_extension(&self, extension: Extension) -> Result<QueryExtensionResponse, Error> {
-        self.sequence.append(ReplyKind::QueryExtension)?;
-
-        let request = QueryExtension {
-            opcode: Opcode::QUERY_EXTENSION,
-            pad0: 0,
-            length: 2 + (extension.len() as u16 + request::pad(extension.len()) as u16) / 4,
-            name_len: extension.len() as u16,
-            pad1: 0,
-        };
-
-        let extension = extension.to_string();
-
-        self.stream.send(
-            &[
-                request::encode(&request).to_vec(),
-                extension.as_bytes().to_vec(),
-                vec![0u8; request::pad(extension.as_bytes().len())],
-            ]
-            .concat(),
-        )?;
+        self.extension_query().query(extension)
+    }
 
-        match self.replies.wait()? {
-            Reply::QueryExtension(response) => Ok(response),
-            _ => unreachable!(),
+    /// what is needed to query extensions without the display, used to enable extensions the first
+    /// time they are needed
+    fn extension_query(&self) -> ExtensionQuery {
+        ExtensionQuery {
+            stream: self.stream.clone(),
+            replies: self.replies.clone(),
+            sequence: self.sequence.clone(),
+            extensions: self.extensions.clone(),
         }
     }
 
@@ -456,6 +1530,7 @@ impl Display {
             self.replies.clone(),
             self.sequence.clone(),
             extension.major_opcode,
+            self.connection.clone(),
         ))
     }
 
@@ -472,89 +1547,254 @@ impl Display {
     /// This request changes the specified dynamic parameters if the pointer is actively grabbed by the
     /// client and the specified time is no earlier than the last-pointer-grab time and no later than the current server time. The interpretation of event-mask and cursor are the same as in GrabPointer.
     /// This request has no effect on the parameters of any passive grabs established with GrabButton.
-    pub fn change_active_pointer_grab(&self, cursor: Cursor, event_mask: Vec<EventMask>) -> Result<(), Error> {
-        self.sequence.skip();
+    pub fn change_active_pointer_grab(
+        &self,
+        cursor: Cursor,
+        event_mask: Vec<EventMask>,
+    ) -> Result<(), Error> {
+        self.stream.send_void_encode(
+            &self.sequence,
+            ChangeActivePointerGrab {
+                opcode: Opcode::CHANGE_ACTIVE_POINTER_GRAB,
+                pad0: 0,
+                length: 4,
+                cursor: cursor as u32,
+                time: 0,
+                event_mask: event_mask.iter().fold(0, |acc, mask| acc | *mask as u16),
+                pad1: 0,
+            },
+        )
+    }
 
-        self.stream.send_encode(ChangeActivePointerGrab {
-            opcode: Opcode::CHANGE_ACTIVE_POINTER_GRAB,
-            pad0: 0,
-            length: 4,
-            cursor: cursor as u32,
-            time: 0,
-            event_mask: event_mask.iter().fold(0, |acc, mask| acc | *mask as u16),
-            pad1: 0,
-        })
+    /// wait until the server has processed every request sent before this call, an error caused
+    /// by one of those requests is returned
+    ///
+    /// requests without a reply are buffered so their errors can only be seen once the server
+    /// has processed them, they are reported by sync or by the event functions such as
+    /// [`Display::next_event`], whichever runs first, errors of requests sent after this call are
+    /// left for a later sync
+    pub fn sync(&self) -> Result<(), Error> {
+        let sequence = self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::GetInputFocus,
+            GetInputFocus {
+                opcode: Opcode::GET_INPUT_FOCUS,
+                pad0: 0,
+                length: 1,
+            },
+        )?;
+
+        self.replies.wait(sequence)?;
+
+        self.replies.poll_error_until(sequence)
+    }
+
+    /// send every request that is still waiting in the output buffer
+    pub fn flush(&self) -> Result<(), Error> {
+        self.stream.flush()
     }
 
     /// this request returns the current focused window
     pub fn get_input_focus(&self) -> Result<GetInputFocusResponse, Error> {
-        self.sequence.append(ReplyKind::GetInputFocus)?;
+        let sequence = self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::GetInputFocus,
+            GetInputFocus {
+                opcode: Opcode::GET_INPUT_FOCUS,
+                pad0: 0,
+                length: 1,
+            },
+        )?;
 
-        self.stream.send_encode(GetInputFocus {
-            opcode: Opcode::GET_INPUT_FOCUS,
-            pad0: 0,
-            length: 1,
-        })?;
+        match self.replies.wait(sequence)? {
+            Reply::GetInputFocus(response) => Ok(response),
+            _ => Err(Error::UnexpectedReply),
+        }
+    }
 
-        match self.replies.wait()? {
+    /// a stream of the events, the stream and [`Display::next_event`] take from the same queue,
+    /// only the task that polled the events last is woken up so a single task should take them
+    #[cfg(feature = "async")]
+    pub fn events(&self) -> future::EventStream {
+        future::EventStream::new(self.stream.clone(), self.events.clone())
+    }
+
+    /// a future for the reply to the request with this sequence
+    #[cfg(feature = "async")]
+    fn reply_future(&self, sequence: u64) -> future::ReplyFuture {
+        future::ReplyFuture::new(self.replies.clone(), sequence)
+    }
+
+    /// this request returns the current focused window without blocking the executor
+    #[cfg(feature = "async")]
+    pub async fn get_input_focus_async(&self) -> Result<GetInputFocusResponse, Error> {
+        let sequence = self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::GetInputFocus,
+            GetInputFocus {
+                opcode: Opcode::GET_INPUT_FOCUS,
+                pad0: 0,
+                length: 1,
+            },
+        )?;
+
+        match self.reply_future(sequence).await? {
             Reply::GetInputFocus(response) => Ok(response),
-            _ => unreachable!(),
+            _ => Err(Error::UnexpectedReply),
+        }
+    }
+
+    /// get an atom from its name without blocking the executor, this uses the same cache as
+    /// [`Display::intern_atom`]
+    #[cfg(feature = "async")]
+    pub async fn intern_atom_async(&self, name: &str, only_if_exists: bool) -> Result<Atom, Error> {
+        match self.cached_atom(name)? {
+            Some(atom) => Ok(atom),
+            None => {
+                let sequence = self.send_intern_atom(name, only_if_exists)?;
+
+                self.interned_atom(name, self.reply_future(sequence).await?)
+            }
+        }
+    }
+
+    /// get the name of an atom without blocking the executor, names are cached just like
+    /// interned atoms
+    #[cfg(feature = "async")]
+    pub async fn get_atom_name_async(&self, atom: Atom) -> Result<String, Error> {
+        match self.cached_atom_name(atom)? {
+            Some(name) => Ok(name),
+            None => {
+                let sequence = self.send_get_atom_name(atom)?;
+
+                self.atom_name(atom, self.reply_future(sequence).await?)
+            }
         }
     }
 
     /// get an atom from its name, this function is cached and will not perform a request if the
     /// atom is in the cache, use clear_atom_cache(), to clear the cache.
     pub fn intern_atom(&self, name: &str, only_if_exists: bool) -> Result<Atom, Error> {
-        if let Some(atom) = self.atom_cache.get(name)? {
-            Ok(atom)
-        } else {
-            self.sequence.append(ReplyKind::InternAtom)?;
+        match self.cached_atom(name)? {
+            Some(atom) => Ok(atom),
+            None => {
+                let sequence = self.send_intern_atom(name, only_if_exists)?;
 
-            let request = InternAtom {
-                opcode: Opcode::INTERN_ATOM,
-                only_if_exists: if only_if_exists { 1 } else { 0 },
-                length: 2 + (name.len() as u16 + request::pad(name.len()) as u16) / 4,
-                name_len: name.len() as u16,
-                pad1: [0u8; 2],
-            };
+                self.wait_intern_atom(name, sequence)
+            }
+        }
+    }
 
-            self.stream.send(
-                &[
-                    request::encode(&request).to_vec(),
-                    name.as_bytes().to_vec(),
-                    vec![0u8; request::pad(name.as_bytes().len())],
-                ]
-                .concat(),
-            )?;
+    /// get the atoms of many names at once, the requests for every atom that is not cached are
+    /// sent before waiting on any of the replies so this only takes a single round trip
+    pub fn intern_atoms(&self, names: &[&str]) -> Result<Vec<Atom>, Error> {
+        let pending = names
+            .iter()
+            .map(|name| match self.cached_atom(name)? {
+                Some(atom) => Ok(Ok(atom)),
+                None => self.send_intern_atom(name, false).map(Err),
+            })
+            .collect::<Result<Vec<Result<Atom, u64>>, Error>>()?;
 
-            match self.replies.wait()? {
-                Reply::InternAtom(response) => match response.atom {
-                    u32::MIN => Err(Error::InvalidAtom),
-                    _ => {
-                        self.atom_cache.insert(name, Atom::new(response.atom))?;
+        // every reply is waited on even if one fails so that none of them are left behind
+        names
+            .iter()
+            .zip(pending)
+            .map(|(name, pending)| match pending {
+                Ok(atom) => Ok(atom),
+                Err(sequence) => self.wait_intern_atom(name, sequence),
+            })
+            .collect::<Vec<Result<Atom, Error>>>()
+            .into_iter()
+            .collect()
+    }
 
-                        Ok(Atom::new(response.atom))
-                    }
-                },
-                _ => unreachable!(),
-            }
+    fn cached_atom(&self, name: &str) -> Result<Option<Atom>, Error> {
+        match Atom::predefined(name) {
+            Some(atom) => Ok(Some(atom)),
+            None => self.atom_cache.get(name),
+        }
+    }
+
+    fn send_intern_atom(&self, name: &str, only_if_exists: bool) -> Result<u64, Error> {
+        let request = InternAtom {
+            opcode: Opcode::INTERN_ATOM,
+            only_if_exists: if only_if_exists { 1 } else { 0 },
+            length: 2 + (name.len() as u16 + request::pad(name.len()) as u16) / 4,
+            name_len: name.len() as u16,
+            pad1: [0u8; 2],
+        };
+
+        self.stream.send_reply(
+            &self.sequence,
+            ReplyKind::InternAtom,
+            &[
+                request::encode(&request),
+                name.as_bytes().to_vec(),
+                vec![0u8; request::pad(name.as_bytes().len())],
+            ]
+            .concat(),
+        )
+    }
+
+    fn wait_intern_atom(&self, name: &str, sequence: u64) -> Result<Atom, Error> {
+        self.interned_atom(name, self.replies.wait(sequence)?)
+    }
+
+    fn interned_atom(&self, name: &str, reply: Reply) -> Result<Atom, Error> {
+        match reply {
+            Reply::InternAtom(response) => match response.atom {
+                u32::MIN => Err(Error::InvalidAtom),
+                _ => {
+                    self.atom_cache.insert(name, Atom::new(response.atom))?;
+
+                    Ok(Atom::new(response.atom))
+                }
+            },
+            _ => Err(Error::UnexpectedReply),
         }
     }
 
-    /// get the name of an atom
+    /// get the name of an atom, names are cached just like interned atoms
     pub fn get_atom_name(&self, atom: Atom) -> Result<String, Error> {
-        self.sequence.append(ReplyKind::GetAtomName)?;
+        match self.cached_atom_name(atom)? {
+            Some(name) => Ok(name),
+            None => {
+                let sequence = self.send_get_atom_name(atom)?;
 
-        self.stream.send_encode(GetAtomName {
-            opcode: Opcode::GET_ATOM_NAME,
-            pad0: 0,
-            length: 2,
-            atom: atom.id(),
-        })?;
+                self.atom_name(atom, self.replies.wait(sequence)?)
+            }
+        }
+    }
+
+    fn cached_atom_name(&self, atom: Atom) -> Result<Option<String>, Error> {
+        match atom.predefined_name() {
+            Some(name) => Ok(Some(name.to_string())),
+            None => self.atom_cache.name(atom),
+        }
+    }
+
+    fn send_get_atom_name(&self, atom: Atom) -> Result<u64, Error> {
+        self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::GetAtomName,
+            GetAtomName {
+                opcode: Opcode::GET_ATOM_NAME,
+                pad0: 0,
+                length: 2,
+                atom: atom.id(),
+            },
+        )
+    }
+
+    fn atom_name(&self, atom: Atom, reply: Reply) -> Result<String, Error> {
+        match reply {
+            Reply::GetAtomName { name } => {
+                self.atom_cache.insert(&name, atom)?;
 
-        match self.replies.wait()? {
-            Reply::GetAtomName { name } => Ok(name),
-            _ => unreachable!(),
+                Ok(name)
+            }
+            _ => Err(Error::UnexpectedReply),
         }
     }
 
@@ -567,18 +1807,20 @@ impl Display {
     /// get the owner of a selection, (this function returns the window id, use
     /// display::window_from_id to get the structure)
     pub fn get_selection_owner(&self, selection: Atom) -> Result<Option<u32>, Error> {
-        self.sequence.append(ReplyKind::GetSelectionOwner)?;
-
-        self.stream.send_encode(GetSelectionOwner {
-            opcode: Opcode::GET_SELECTION_OWNER,
-            pad0: 0,
-            length: 2,
-            selection: selection.id(),
-        })?;
+        let sequence = self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::GetSelectionOwner,
+            GetSelectionOwner {
+                opcode: Opcode::GET_SELECTION_OWNER,
+                pad0: 0,
+                length: 2,
+                selection: selection.id(),
+            },
+        )?;
 
-        match self.replies.wait()? {
+        match self.replies.wait(sequence)? {
             Reply::GetSelectionOwner(response) => Ok((response.owner != 0).then(|| response.owner)),
-            _ => unreachable!(),
+            _ => Err(Error::UnexpectedReply),
         }
     }
 
@@ -609,21 +1851,22 @@ impl Display {
         src_window: Option<Window>,
         dst_window: Option<Window>,
     ) -> Result<(), Error> {
-        self.sequence.skip();
-
-        self.stream.send_encode(WarpPointer {
-            opcode: Opcode::WARP_POINTER,
-            pad0: 0,
-            length: 6,
-            src_window: src_window.map(|window| window.id()).unwrap_or(0),
-            dst_window: dst_window.map(|window| window.id()).unwrap_or(0),
-            src_x,
-            src_y,
-            src_width,
-            src_height,
-            dst_x,
-            dst_y,
-        })
+        self.stream.send_void_encode(
+            &self.sequence,
+            WarpPointer {
+                opcode: Opcode::WARP_POINTER,
+                pad0: 0,
+                length: 6,
+                src_window: src_window.map(|window| window.id()).unwrap_or(0),
+                dst_window: dst_window.map(|window| window.id()).unwrap_or(0),
+                src_x,
+                src_y,
+                src_width,
+                src_height,
+                dst_x,
+                dst_y,
+            },
+        )
     }
 
     /// This request is a wrapper around warp pointer that allows you to only warp the pointer
@@ -634,35 +1877,38 @@ impl Display {
 
     /// This request releases the keyboard if this client has it actively grabbed (as a result of either GrabKeyboard or GrabKey) and releases any queued events (server side).
     pub fn ungrab_keyboard(&self) -> Result<(), Error> {
-        self.sequence.skip();
-
-        self.stream.send_encode(UngrabKeyboard {
-            opcode: Opcode::UNGRAB_KEYBOARD,
-            pad0: 0,
-            length: 2,
-            time: 0,
-        })
+        self.stream.send_void_encode(
+            &self.sequence,
+            UngrabKeyboard {
+                opcode: Opcode::UNGRAB_KEYBOARD,
+                pad0: 0,
+                length: 2,
+                time: 0,
+            },
+        )
     }
 
     /// get the keyboard mapping from the server
     pub fn get_keyboard_mapping(&self) -> Result<(Vec<Keysym>, u8), Error> {
-        self.sequence.append(ReplyKind::GetKeyboardMapping)?;
-
-        self.stream.send_encode(GetKeyboardMapping {
-            opcode: Opcode::GET_KEYBOARD_MAPPING,
-            pad0: 0,
-            length: 2,
-            first: self.setup.min_keycode,
-            count: self.setup.max_keycode - self.setup.min_keycode + 1,
-            pad1: [0u8; 2],
-        })?;
+        let sequence = self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::GetKeyboardMapping,
+            GetKeyboardMapping {
+                opcode: Opcode::GET_KEYBOARD_MAPPING,
+                pad0: 0,
+                length: 2,
+                first: self.setup.min_keycode,
+                count: self.setup.max_keycode - self.setup.min_keycode + 1,
+                pad1: [0u8; 2],
+            },
+        )?;
 
-        match self.replies.wait()? {
+        match self.replies.wait(sequence)? {
             Reply::GetKeyboardMapping {
                 keysyms,
                 keysyms_per_keycode,
             } => Ok((keysyms, keysyms_per_keycode)),
-            _ => unreachable!(),
+            _ => Err(Error::UnexpectedReply),
         }
     }
 
@@ -700,37 +1946,39 @@ impl Display {
 
     /// ungrab the pointer
     pub fn ungrab_pointer(&self) -> Result<(), Error> {
-        self.sequence.skip();
-
         // TODO: un-hardcode current time
 
-        self.stream.send_encode(UngrabPointer {
-            opcode: Opcode::UNGRAB_POINTER,
-            pad0: 0,
-            length: 2,
-            time: 0,
-        })?;
-
-        self.replies.poll_error()
+        self.stream.send_void_encode(
+            &self.sequence,
+            UngrabPointer {
+                opcode: Opcode::UNGRAB_POINTER,
+                pad0: 0,
+                length: 2,
+                time: 0,
+            },
+        )
     }
 
+    /// every request is encoded in little endian byte order no matter the byte order of the host
     fn endian(&self) -> u8 {
-        cfg!(target_endian = "little")
-            .then(|| 0x6c)
-            .unwrap_or_else(|| 0x42)
+        0x6c
     }
 
-    fn read_setup(&mut self) -> Result<(), Error> {
+    fn read_setup(
+        &mut self,
+        response: SetupResponse,
+        timeout: Option<Duration>,
+    ) -> Result<(), Error> {
         self.setup = self.stream.recv_decode()?;
 
-        let _vendor = self.stream.recv_str(self.setup.vendor_len as usize)?;
+        let vendor = self.stream.recv_str(self.setup.vendor_len as usize)?;
 
         let bytes = self
             .stream
-            .recv(std::mem::size_of::<PixmapFormat>() * self.setup.pixmap_formats_len as usize)?;
+            .recv(PixmapFormat::SIZE * self.setup.pixmap_formats_len as usize)?;
 
-        let _formats: &[PixmapFormat] =
-            request::decode_slice(&bytes, self.setup.pixmap_formats_len as usize);
+        let pixmap_formats: Vec<PixmapFormat> =
+            request::decode_vec(&bytes, self.setup.pixmap_formats_len as usize)?;
 
         for _ in 0..self.setup.roots_len {
             let mut screen = Screen::new(self.stream.recv_decode()?);
@@ -740,9 +1988,9 @@ impl Display {
 
                 let bytes = self
                     .stream
-                    .recv(std::mem::size_of::<VisualResponse>() * depth.length as usize)?;
+                    .recv(VisualResponse::SIZE * depth.length as usize)?;
 
-                depth.extend(request::decode_slice(&bytes, depth.length as usize));
+                depth.extend(&request::decode_vec(&bytes, depth.length as usize)?)?;
 
                 screen.depths.push(depth);
             }
@@ -750,49 +1998,115 @@ impl Display {
             self.roots.push(screen);
         }
 
+        // the listener blocks on reads for as long as the connection is open
+        if timeout.is_some() {
+            self.stream.set_read_timeout(None)?;
+        }
+
         let stream = self.stream.clone();
         let events = self.events.clone();
         let replies = self.replies.clone();
         let sequence = self.sequence.clone();
         let roots = self.roots.clone();
+        let extensions = self.extensions.clone();
 
-        thread::spawn(move || {
-            let mut listener = EventListener::new(stream, events, replies.clone(), sequence, roots);
+        let on_disconnect = self.connection.on_disconnect.clone();
 
-            if let Err(err) = listener.listen() {
-                let _ = replies.push_error(err);
-            }
-        });
+        let listener = EventListener::new(
+            stream.clone(),
+            events.clone(),
+            replies.clone(),
+            sequence,
+            roots,
+            extensions,
+        );
+
+        // in single threaded mode the threads that wait on replies and events read them
+        if self.connection.single_threaded {
+            self.signal.set_driver(Some(Arc::new(InlineListener {
+                listener: Mutex::new(listener),
+                stream,
+                events,
+                replies,
+                signal: self.signal.clone(),
+                on_disconnect,
+            })))?;
+        } else {
+            let listener = thread::spawn(move || {
+                let mut listener = listener;
 
-        xid::setup(self.setup.resource_id_base, self.setup.resource_id_mask)?;
+                let error = listener.listen().err().unwrap_or(Error::ConnectionClosed);
+
+                disconnect(&stream, &events, &replies, &on_disconnect, error);
+            });
+
+            *lock!(self.connection.listener)? = Some(listener);
+        }
+
+        self.xid
+            .setup(self.setup.resource_id_base, self.setup.resource_id_mask)?;
+
+        self.stream
+            .set_max_request_length(self.setup.maximum_request_len as usize);
+
+        // big requests and xc-misc are only enabled once they are needed
+        self.stream.set_big_requests(Some(self.extension_query()))?;
+        self.xid.set_xc_misc(self.extension_query())?;
+
+        self.info = Arc::new(SetupInfo {
+            protocol_major_version: response.major_version,
+            protocol_minor_version: response.minor_version,
+            release_number: self.setup.release_number,
+            vendor,
+            maximum_request_length: self.stream.max_request_length() as u32,
+            image_byte_order: ImageByteOrder::try_from(self.setup.image_byte_order)?,
+            bitmap_bit_order: BitmapBitOrder::try_from(self.setup.bitmap_format_bit_order)?,
+            bitmap_scanline_unit: self.setup.bitmap_format_scanline_unit,
+            bitmap_scanline_pad: self.setup.bitmap_format_scanline_pad,
+            motion_buffer_size: self.setup.motion_buffer_size,
+            pixmap_formats,
+            min_keycode: self.setup.min_keycode,
+            max_keycode: self.setup.max_keycode,
+        });
 
         Ok(())
     }
 
-    fn setup<'a>(&mut self) -> Result<(), Error> {
-        let entry = auth::entry()?;
+    fn setup<'a>(
+        &mut self,
+        auth: Option<(Vec<u8>, Vec<u8>)>,
+        timeout: Option<Duration>,
+    ) -> Result<(), Error> {
+        // without authorization the server decides if the connection is allowed
+        let (name, data) = auth.unwrap_or_default();
+
+        if timeout.is_some() {
+            self.stream.set_read_timeout(timeout)?;
+        }
 
         let request = SetupRequest::new(
             self.endian(),
             X_PROTOCOL,
             X_PROTOCOL_REVISION,
-            entry.name.len() as u16,
-            entry.data.len() as u16,
+            name.len() as u16,
+            data.len() as u16,
         );
 
-        self.stream.send(request::encode(&request))?;
+        self.stream.send(&request::encode(&request))?;
 
         self.stream.send_arr(&[
-            entry.name.clone(),
-            vec![0u8; request::pad(entry.name.len())],
-            entry.data.clone(),
-            vec![0u8; request::pad(entry.data.len())],
+            name.clone(),
+            vec![0u8; request::pad(name.len())],
+            data.clone(),
+            vec![0u8; request::pad(data.len())],
         ])?;
 
+        self.stream.flush()?;
+
         let response: SetupResponse = self.stream.recv_decode()?;
 
         match response.status {
-            1 => self.read_setup(),
+            1 => self.read_setup(response, timeout),
             0 => Err(Error::SetupFailed {
                 reason: self.stream.recv_str(response.padding as usize)?,
             }),
@@ -803,144 +2117,221 @@ impl Display {
 }
 
 macro_rules! handle_reply {
-    ($self:ident, $response:ty, $reply:ident) => {
-        let response: $response = $self.stream.recv_decode()?;
+    ($self:ident, $sequence:expr, $response:ty, $reply:ident) => {
+        let response: $response = $self.reader.recv_decode()?;
 
-        $self.replies.push(Reply::$reply(response))?;
+        $self.replies.push($sequence, Reply::$reply(response))?;
     };
 }
 
 pub struct EventListener {
-    stream: Stream,
+    reader: Reader,
     events: Queue<Event>,
-    replies: Queue<Reply>,
+    replies: Replies,
     sequence: SequenceManager,
     roots: Roots,
+    extensions: Arc<Mutex<HashMap<u8, String>>>,
 }
 
 impl EventListener {
     pub fn new(
         stream: Stream,
         events: Queue<Event>,
-        replies: Queue<Reply>,
+        replies: Replies,
         sequence: SequenceManager,
         roots: Roots,
+        extensions: Arc<Mutex<HashMap<u8, String>>>,
     ) -> EventListener {
         EventListener {
-            stream,
+            reader: Reader::new(stream),
             events,
             replies,
             sequence,
             roots,
+            extensions,
         }
     }
 
-    fn handle_reply(&mut self, event: GenericEvent) -> Result<(), Error> {
-        let sequence = self.sequence.get(event.sequence)?;
+    fn handle_reply(&mut self, event: GenericEvent, id: u64) -> Result<(), Error> {
+        let sequence = self.sequence.get(id)?;
+
+        // a reply that can not be decoded is reported to the thread waiting on it
+        match self.read_reply(event, &sequence) {
+            Ok(()) => Ok(()),
+            Err(error) => self.replies.push_sequence_error(sequence.id, error),
+        }
+    }
 
+    fn read_reply(&mut self, event: GenericEvent, sequence: &Sequence) -> Result<(), Error> {
         match sequence.kind {
+            ReplyKind::Discard => {
+                let _: GetInputFocusResponse = self.reader.recv_decode()?;
+            }
             ReplyKind::InternAtom => {
-                handle_reply!(self, InternAtomResponse, InternAtom);
-            },
+                handle_reply!(self, sequence.id, InternAtomResponse, InternAtom);
+            }
             ReplyKind::GetWindowAttributes => {
-                handle_reply!(self, GetWindowAttributesResponse, GetWindowAttributes);
+                handle_reply!(
+                    self,
+                    sequence.id,
+                    GetWindowAttributesResponse,
+                    GetWindowAttributes
+                );
             }
             ReplyKind::GetGeometry => {
-                handle_reply!(self, GetGeometryResponse, GetGeometry);
+                handle_reply!(self, sequence.id, GetGeometryResponse, GetGeometry);
             }
             ReplyKind::GrabPointer => {
-                handle_reply!(self, GrabPointerResponse, GrabPointer);
+                handle_reply!(self, sequence.id, GrabPointerResponse, GrabPointer);
             }
             ReplyKind::QueryPointer => {
-                handle_reply!(self, QueryPointerResponse, QueryPointer);
+                handle_reply!(self, sequence.id, QueryPointerResponse, QueryPointer);
             }
             ReplyKind::QueryExtension => {
-                handle_reply!(self, QueryExtensionResponse, QueryExtension);
+                handle_reply!(self, sequence.id, QueryExtensionResponse, QueryExtension);
             }
             ReplyKind::GetSelectionOwner => {
-                handle_reply!(self, GetSelectionOwnerResponse, GetSelectionOwner);
+                handle_reply!(
+                    self,
+                    sequence.id,
+                    GetSelectionOwnerResponse,
+                    GetSelectionOwner
+                );
             }
             #[cfg(feature = "xinerama")]
             ReplyKind::XineramaIsActive => {
-                handle_reply!(self, XineramaIsActiveResponse, XineramaIsActive);
+                handle_reply!(
+                    self,
+                    sequence.id,
+                    XineramaIsActiveResponse,
+                    XineramaIsActive
+                );
             }
             #[cfg(feature = "xinerama")]
             ReplyKind::XineramaQueryScreens => {
-                let response: XineramaQueryScreensResponse = self.stream.recv_decode()?;
+                let response: XineramaQueryScreensResponse = self.reader.recv_decode()?;
 
                 let mut screens: Vec<XineramaScreenInfo> = Vec::new();
 
                 for _ in 0..response.number {
-                    screens.push(self.stream.recv_decode()?);
+                    screens.push(self.reader.recv_decode()?);
                 }
 
-                self.replies.push(Reply::XineramaQueryScreens { screens })?;
+                self.replies
+                    .push(sequence.id, Reply::XineramaQueryScreens { screens })?;
+            }
+            ReplyKind::BigRequestsEnable => {
+                handle_reply!(
+                    self,
+                    sequence.id,
+                    BigRequestsEnableResponse,
+                    BigRequestsEnable
+                );
+            }
+            ReplyKind::XcMiscGetXidRange => {
+                handle_reply!(
+                    self,
+                    sequence.id,
+                    XcMiscGetXidRangeResponse,
+                    XcMiscGetXidRange
+                );
+            }
+            ReplyKind::XcMiscGetXidList => {
+                let response: XcMiscGetXidListResponse = self.reader.recv_decode()?;
+
+                let bytes = self.reader.recv(4 * response.ids_len as usize)?;
+
+                self.replies.push(
+                    sequence.id,
+                    Reply::XcMiscGetXidList {
+                        ids: request::decode_vec(&bytes, response.ids_len as usize)?,
+                    },
+                )?;
             }
             ReplyKind::GetInputFocus => {
-                handle_reply!(self, GetInputFocusResponse, GetInputFocus);
+                handle_reply!(self, sequence.id, GetInputFocusResponse, GetInputFocus);
             }
             ReplyKind::GrabKeyboard => {
-                let _: GrabKeyboardResponse = self.stream.recv_decode()?;
+                let _: GrabKeyboardResponse = self.reader.recv_decode()?;
 
-                self.replies.push(Reply::GrabKeyboard(GrabKeyboardStatus::from(event.detail)))?;
-            },
+                self.replies.push(
+                    sequence.id,
+                    Reply::GrabKeyboard(GrabKeyboardStatus::from(event.detail)),
+                )?;
+            }
             ReplyKind::QueryTree => {
-                let response: QueryTreeResponse = self.stream.recv_decode()?;
-
-                let bytes = self.stream.recv(response.num_children as usize * 4)?;
-
-                self.replies.push(Reply::QueryTree(TreeNode {
-                    root: response.root,
-                    parent: response.parent,
-                    children: bytes.chunks(4)
-                        .filter(|bytes| bytes.len() == 4)
-                        .map(|bytes| u32::from_le_bytes(bytes.try_into().expect("failed to convert")))
-                        .collect::<Vec<u32>>(),
-                }))?;
-            },
+                let response: QueryTreeResponse = self.reader.recv_decode()?;
+
+                let bytes = self.reader.recv(response.num_children as usize * 4)?;
+
+                self.replies.push(
+                    sequence.id,
+                    Reply::QueryTree(TreeNode {
+                        root: response.root,
+                        parent: response.parent,
+                        children: request::decode_vec(&bytes, response.num_children as usize)?,
+                    }),
+                )?;
+            }
             ReplyKind::GetAtomName => {
-                let response: GetAtomNameResponse = self.stream.recv_decode()?;
+                let response: GetAtomNameResponse = self.reader.recv_decode()?;
 
-                let bytes = self.stream.recv(response.name_len as usize)?;
+                let bytes = self.reader.recv(response.name_len as usize)?;
 
-                self.replies.push(Reply::GetAtomName {
-                    name: String::from_utf8(bytes).map_err(|_| Error::Utf8)?,
-                })?;
+                self.replies.push(
+                    sequence.id,
+                    Reply::GetAtomName {
+                        name: String::from_utf8(bytes).map_err(|_| Error::Utf8)?,
+                    },
+                )?;
 
-                self.stream.recv(request::pad(response.name_len as usize))?;
+                self.reader.recv(request::pad(response.name_len as usize))?;
             }
             ReplyKind::GetProperty => {
-                let response: GetPropertyResponse = self.stream.recv_decode()?;
+                let response: GetPropertyResponse = self.reader.recv_decode()?;
 
                 let value_size = match event.detail {
                     0 => 0,
                     8 => response.value_len as usize,
                     16 => response.value_len as usize * 2,
                     32 => response.value_len as usize * 4,
-                    _ => unreachable!(),
+                    format => {
+                        return Err(Error::InvalidValue {
+                            name: "property format",
+                            value: format as u32,
+                        })
+                    }
                 };
 
-                self.replies.push(Reply::GetProperty {
-                    type_: Atom::new(response.type_),
-                    value: self.stream.recv(value_size)?,
-                })?;
+                self.replies.push(
+                    sequence.id,
+                    Reply::GetProperty {
+                        type_: Atom::new(response.type_),
+                        format: event.detail,
+                        bytes_after: response.bytes_after,
+                        value: self.reader.recv(value_size)?,
+                    },
+                )?;
 
-                self.stream.recv(request::pad(value_size))?;
+                self.reader.recv(request::pad(value_size))?;
             }
             ReplyKind::GetKeyboardMapping => {
-                let response: KeyboardMappingResponse = self.stream.recv_decode()?;
+                let response: KeyboardMappingResponse = self.reader.recv_decode()?;
 
-                let bytes = self.stream.recv(4 * response.length as usize)?;
+                let bytes = self.reader.recv(4 * response.length as usize)?;
 
-                let keysyms = request::decode_slice::<u32>(&bytes, response.length as usize);
+                let keysyms: Vec<u32> = request::decode_vec(&bytes, response.length as usize)?;
 
-                self.replies.push(Reply::GetKeyboardMapping {
-                    keysyms: keysyms
-                        .iter()
-                        .map(|value| Keysym::new(*value))
-                        .collect::<Vec<Keysym>>(),
-                    keysyms_per_keycode: event.detail,
-                })?;
+                self.replies.push(
+                    sequence.id,
+                    Reply::GetKeyboardMapping {
+                        keysyms: keysyms
+                            .iter()
+                            .map(|value| Keysym::new(*value))
+                            .collect::<Vec<Keysym>>(),
+                        keysyms_per_keycode: event.detail,
+                    },
+                )?;
             }
         }
 
@@ -950,32 +2341,39 @@ impl EventListener {
     // TODO: there is a lot of repetition here, it may be possible to procedurally generate this
     // through macros instead
 
-    fn handle_event(&mut self, generic: GenericEvent) -> Result<(), Error> {
+    fn handle_event(&mut self, generic: GenericEvent, sequence: u64) -> Result<(), Error> {
         match generic.opcode & 0b0111111 {
             Response::ERROR => {
-                let error: ErrorEvent = self.stream.recv_decode()?;
+                let error: ErrorEvent = self.reader.recv_decode()?;
 
-                self.replies.push_error(Error::Event {
+                let error = Error::Event {
                     error: ErrorCode::from(generic.detail),
                     major_opcode: error.major_opcode,
                     minor_opcode: error.minor_opcode,
                     bad_value: error.bad_value,
                     sequence: generic.sequence,
-                })
+                    request: Opcode::name(error.major_opcode),
+                    extension: lock!(self.extensions)?.get(&error.major_opcode).cloned(),
+                };
+
+                // an error for a request that expects a reply is sent to the thread waiting on it
+                match self.sequence.get(sequence) {
+                    Ok(sequence) => self.replies.push_sequence_error(sequence.id, error),
+                    Err(_) => self.replies.push_error(sequence, error),
+                }
             }
             Response::REPLY => {
-                self.handle_reply(generic)?;
+                self.handle_reply(generic, sequence)?;
 
                 Ok(())
             }
             Response::KEY_PRESS | Response::KEY_RELEASE => {
-                let key_event: KeyEvent = self.stream.recv_decode()?;
+                let key_event: KeyEvent = self.reader.recv_decode()?;
 
                 self.events.push(Event::KeyEvent {
                     kind: match generic.opcode & 0b0111111 {
                         Response::KEY_PRESS => EventKind::Press,
-                        Response::KEY_RELEASE => EventKind::Release,
-                        _ => unreachable!(),
+                        _ => EventKind::Release,
                     },
                     coordinates: Coordinates::new(
                         key_event.event_x,
@@ -992,13 +2390,12 @@ impl EventListener {
                 })
             }
             Response::BUTTON_PRESS | Response::BUTTON_RELEASE => {
-                let button_event: ButtonEvent = self.stream.recv_decode()?;
+                let button_event: ButtonEvent = self.reader.recv_decode()?;
 
                 self.events.push(Event::ButtonEvent {
                     kind: match generic.opcode & 0b0111111 {
                         Response::BUTTON_PRESS => EventKind::Press,
-                        Response::BUTTON_RELEASE => EventKind::Release,
-                        _ => unreachable!(),
+                        _ => EventKind::Release,
                     },
                     coordinates: Coordinates::new(
                         button_event.event_x,
@@ -1015,7 +2412,7 @@ impl EventListener {
                 })
             }
             Response::MOTION_NOTIFY => {
-                let motion_notify: MotionNotify = self.stream.recv_decode()?;
+                let motion_notify: MotionNotify = self.reader.recv_decode()?;
 
                 self.events.push(Event::MotionNotify {
                     coordinates: Coordinates::new(
@@ -1032,7 +2429,7 @@ impl EventListener {
                 })
             }
             Response::ENTER_NOTIFY => {
-                let event: EnterNotify = self.stream.recv_decode()?;
+                let event: EnterNotify = self.reader.recv_decode()?;
 
                 self.events.push(Event::EnterNotify {
                     root: event.root,
@@ -1045,31 +2442,31 @@ impl EventListener {
                         event.root_y,
                     ),
                     state: event.state,
-                    mode: EnterMode::from(event.mode),
+                    mode: EnterMode::try_from(event.mode)?,
                     focus: (event.sf & 0x01) != 0,
                     same_screen: (event.sf & 0x02) != 0,
                 })
             }
             Response::FOCUS_IN => {
-                let event: FocusIn = self.stream.recv_decode()?;
+                let event: FocusIn = self.reader.recv_decode()?;
 
                 self.events.push(Event::FocusIn {
                     detail: FocusDetail::from(generic.detail),
-                    mode: FocusMode::from(event.mode),
+                    mode: FocusMode::try_from(event.mode)?,
                     window: event.event,
                 })
             }
             Response::FOCUS_OUT => {
-                let event: FocusOut = self.stream.recv_decode()?;
+                let event: FocusOut = self.reader.recv_decode()?;
 
                 self.events.push(Event::FocusOut {
                     detail: FocusDetail::from(generic.detail),
-                    mode: FocusMode::from(event.mode),
+                    mode: FocusMode::try_from(event.mode)?,
                     window: event.event,
                 })
             }
             Response::CREATE_NOTIFY => {
-                let event: CreateNotify = self.stream.recv_decode()?;
+                let event: CreateNotify = self.reader.recv_decode()?;
 
                 self.events.push(Event::CreateNotify {
                     parent: event.event,
@@ -1081,7 +2478,7 @@ impl EventListener {
                 })
             }
             Response::DESTROY_NOTIFY => {
-                let event: DestroyNotify = self.stream.recv_decode()?;
+                let event: DestroyNotify = self.reader.recv_decode()?;
 
                 self.events.push(Event::DestroyNotify {
                     event: event.event,
@@ -1089,7 +2486,7 @@ impl EventListener {
                 })
             }
             Response::UNMAP_NOTIFY => {
-                let event: UnmapNotify = self.stream.recv_decode()?;
+                let event: UnmapNotify = self.reader.recv_decode()?;
 
                 self.events.push(Event::UnmapNotify {
                     event: event.event,
@@ -1098,7 +2495,7 @@ impl EventListener {
                 })
             }
             Response::MAP_NOTIFY => {
-                let event: MapNotify = self.stream.recv_decode()?;
+                let event: MapNotify = self.reader.recv_decode()?;
 
                 self.events.push(Event::MapNotify {
                     event: event.event,
@@ -1107,7 +2504,7 @@ impl EventListener {
                 })
             }
             Response::MAP_REQUEST => {
-                let event: MapReq = self.stream.recv_decode()?;
+                let event: MapReq = self.reader.recv_decode()?;
 
                 self.events.push(Event::MapRequest {
                     parent: event.parent,
@@ -1115,7 +2512,7 @@ impl EventListener {
                 })
             }
             Response::REPARENT_NOTIFY => {
-                let event: ReparentNotify = self.stream.recv_decode()?;
+                let event: ReparentNotify = self.reader.recv_decode()?;
 
                 self.events.push(Event::ReparentNotify {
                     event: event.event,
@@ -1127,7 +2524,7 @@ impl EventListener {
                 })
             }
             Response::CONFIGURE_NOTIFY => {
-                let event: ConfigNotify = self.stream.recv_decode()?;
+                let event: ConfigNotify = self.reader.recv_decode()?;
 
                 self.events.push(Event::ConfigureNotify {
                     event: event.event,
@@ -1142,7 +2539,7 @@ impl EventListener {
                 })
             }
             Response::CONFIGURE_REQUEST => {
-                let event: ConfigReq = self.stream.recv_decode()?;
+                let event: ConfigReq = self.reader.recv_decode()?;
 
                 let mut values: Vec<ConfigureValue> = Vec::new();
 
@@ -1166,7 +2563,7 @@ impl EventListener {
                 })
             }
             Response::GRAVITY_NOTIFY => {
-                let event: GravityNotify = self.stream.recv_decode()?;
+                let event: GravityNotify = self.reader.recv_decode()?;
 
                 self.events.push(Event::GravityNotify {
                     event: event.event,
@@ -1176,35 +2573,35 @@ impl EventListener {
                 })
             }
             Response::CIRCULATE_NOTIFY => {
-                let event: CircNotify = self.stream.recv_decode()?;
+                let event: CircNotify = self.reader.recv_decode()?;
 
                 self.events.push(Event::CirculateNotify {
                     event: event.event,
                     window: event.window,
-                    place: Place::from(event.place),
+                    place: Place::try_from(event.place)?,
                 })
             }
             Response::CIRCULATE_REQUEST => {
-                let event: CircReq = self.stream.recv_decode()?;
+                let event: CircReq = self.reader.recv_decode()?;
 
                 self.events.push(Event::CirculateRequest {
                     parent: event.event,
                     window: event.window,
-                    place: Place::from(event.place),
+                    place: Place::try_from(event.place)?,
                 })
             }
             Response::PROPERTY_NOTIFY => {
-                let event: PropertyNotify = self.stream.recv_decode()?;
+                let event: PropertyNotify = self.reader.recv_decode()?;
 
                 self.events.push(Event::PropertyNotify {
                     window: event.window,
                     atom: Atom::new(event.atom),
                     time: event.time,
-                    state: PropertyState::from(event.state),
+                    state: PropertyState::try_from(event.state)?,
                 })
             }
             Response::SELECTION_CLEAR => {
-                let event: SelectionClear = self.stream.recv_decode()?;
+                let event: SelectionClear = self.reader.recv_decode()?;
 
                 self.events.push(Event::SelectionClear {
                     time: event.time,
@@ -1213,7 +2610,7 @@ impl EventListener {
                 })
             }
             Response::SELECTION_REQUEST => {
-                let event: SelectionReq = self.stream.recv_decode()?;
+                let event: SelectionReq = self.reader.recv_decode()?;
 
                 self.events.push(Event::SelectionRequest {
                     time: event.time,
@@ -1224,7 +2621,7 @@ impl EventListener {
                 })
             }
             Response::SELECTION_NOTIFY => {
-                let event: SelectionNotify = self.stream.recv_decode()?;
+                let event: SelectionNotify = self.reader.recv_decode()?;
 
                 self.events.push(Event::SelectionNotify {
                     time: event.time,
@@ -1237,8 +2634,8 @@ impl EventListener {
                 Ok(())
             }
             Response::CLIENT_MESSAGE => {
-                let event: ClientMessage = self.stream.recv_decode()?;
-                let data: [u8; 20] = self.stream.recv_decode()?;
+                let event: ClientMessage = self.reader.recv_decode()?;
+                let data: [u8; 20] = self.reader.recv_decode()?;
 
                 self.events.push(Event::ClientMessage {
                     format: generic.detail,
@@ -1248,7 +2645,7 @@ impl EventListener {
                 })
             }
             Response::MAPPING_NOTIFY => {
-                let event: MappingNotify = self.stream.recv_decode()?;
+                let event: MappingNotify = self.reader.recv_decode()?;
 
                 self.events.push(Event::MappingNotify {
                     request: event.request,
@@ -1260,43 +2657,261 @@ impl EventListener {
         }
     }
 
+    /// widen the sequence number of a response and drop the pending requests before it, the
+    /// threads waiting on them are woken up with an error as they will never get a reply
+    fn widen_sequence(&mut self, generic: &GenericEvent) -> Result<u64, Error> {
+        // keymap notify is the only response without a sequence number
+        if generic.opcode & 0b0111111 == Response::KEYMAP_NOTIFY {
+            return Ok(self.sequence.last_read());
+        }
+
+        let sequence = self.sequence.widen(generic.sequence);
+
+        for stale in self.sequence.prune(sequence)? {
+            self.replies
+                .push_sequence_error(stale.id, Error::ReplyDiscarded { sequence: stale.id })?;
+        }
+
+        Ok(sequence)
+    }
+
     pub fn listen(&mut self) -> Result<(), Error> {
         loop {
-            let event: GenericEvent = self.stream.recv_decode()?;
+            self.read_response()?;
+        }
+    }
+
+    /// read and handle a single response
+    pub fn read_response(&mut self) -> Result<(), Error> {
+        self.reader.next_response()?;
 
-            self.handle_event(event)?;
+        let event: GenericEvent = self.reader.recv_decode()?;
+
+        let sequence = self.widen_sequence(&event)?;
+
+        // the next response starts at a known offset so a response that can not be decoded is
+        // reported and skipped instead of stopping the listener
+        if let Err(error) = self.handle_event(event, sequence) {
+            self.replies.push_error(sequence, error)?;
         }
+
+        Ok(())
     }
 }
 
-fn open_tcp<'a>(host: SocketAddr) -> Result<Display, Error> {
-    let tcp_stream = TcpStream::connect(host).map_err(|_| Error::Stream)?;
+/// options for connecting to the x11 server
+///
+/// # Example: connect with explicit authorization and a timeout
+///
+/// ```no_run
+/// use yaxi::display::ConnectOptions;
+/// use std::time::Duration;
+///
+/// fn main() -> Result<(), Box<dyn std::error::Error>> {
+///     let _display = ConnectOptions::new()
+///         .display(":1")
+///         .auth(b"MIT-MAGIC-COOKIE-1", &[0; 16])
+///         .screen(0)
+///         .connect_timeout(Duration::from_secs(1))
+///         .setup_timeout(Duration::from_secs(5))
+///         .open()?;
+///
+///     Ok(())
+/// }
+/// ```
+#[derive(Debug, Clone, Default)]
+pub struct ConnectOptions {
+    display: Option<String>,
+    auth: Option<(Vec<u8>, Vec<u8>)>,
+    screen: Option<usize>,
+    connect_timeout: Option<Duration>,
+    setup_timeout: Option<Duration>,
+    single_threaded: bool,
+}
 
-    tcp_stream
-        .set_nonblocking(false)
-        .map_err(|_| Error::Stream)?;
+impl ConnectOptions {
+    pub fn new() -> ConnectOptions {
+        ConnectOptions::default()
+    }
 
-    Display::connect(Stream::new(
-        Arc::new(Mutex::new(
-            tcp_stream.try_clone().map_err(|_| Error::Stream)?,
-        )),
-        Arc::new(Mutex::new(tcp_stream)),
-    ))
-}
+    /// the display to connect to in the $DISPLAY format, $DISPLAY is used if this is not set
+    pub fn display(mut self, display: &str) -> ConnectOptions {
+        self.display = Some(display.to_string());
+        self
+    }
+
+    /// authorize with the provided protocol name and data instead of the xauthority file, empty
+    /// name and data connect without authorization
+    pub fn auth(mut self, name: &[u8], data: &[u8]) -> ConnectOptions {
+        self.auth = Some((name.to_vec(), data.to_vec()));
+        self
+    }
+
+    /// the default screen, this overrides the screen from the display string
+    pub fn screen(mut self, screen: usize) -> ConnectOptions {
+        self.screen = Some(screen);
+        self
+    }
+
+    /// connect without a listener thread, replies and events are read by the threads that wait
+    /// for them and [`Display::event_fd`] is the socket itself
+    ///
+    /// nothing is read while no thread waits, events are only queued when the display is asked
+    /// for them which keeps the order of reads deterministic
+    pub fn single_threaded(mut self, single_threaded: bool) -> ConnectOptions {
+        self.single_threaded = single_threaded;
+        self
+    }
+
+    /// the maximum time to wait for a tcp connection to be established
+    pub fn connect_timeout(mut self, timeout: Duration) -> ConnectOptions {
+        self.connect_timeout = Some(timeout);
+        self
+    }
+
+    /// the maximum time to wait for the server while setting up the connection, this only
+    /// applies to streams that support read timeouts
+    pub fn setup_timeout(mut self, timeout: Duration) -> ConnectOptions {
+        self.setup_timeout = Some(timeout);
+        self
+    }
+
+    /// open a connection to the display
+    pub fn open(&self) -> Result<Display, Error> {
+        let info = parse::parse(self.display.as_deref())?;
+
+        let (stream, address) = match (info.protocol, info.host.is_empty()) {
+            (Protocol::TcpSocket, true) => self.open_tcp("localhost", X_TCP_PORT + info.display)?,
+            (Protocol::TcpSocket, false) => self.open_tcp(&info.host, X_TCP_PORT + info.display)?,
+            // the host of a unix connection is only used if it is the path of the socket
+            (Protocol::UnixSocket, _) if info.host.starts_with('/') => {
+                self.open_unix(&info.host)?
+            }
+            (Protocol::UnixSocket, _) => {
+                let path = format!("/tmp/.X11-unix/X{}", info.display);
+
+                // like libxcb the abstract socket is tried before the filesystem path
+                match self.open_abstract(&path) {
+                    Err(err)
+                        if matches!(
+                            err.kind(),
+                            ErrorKind::NotFound
+                                | ErrorKind::ConnectionRefused
+                                | ErrorKind::Unsupported
+                        ) =>
+                    {
+                        self.open_unix(&path)?
+                    }
+                    result => result?,
+                }
+            }
+        };
+
+        self.handshake(stream, &address, info.display, info.screen as usize)
+    }
+
+    /// connect over a caller supplied stream such as an inherited file descriptor or one end of
+    /// a socket pair, the reader and writer usually refer to the same stream
+    pub fn connect<S: Streamable + 'static>(&self, reader: S, writer: S) -> Result<Display, Error> {
+        self.connect_stream(Stream::new(
+            Arc::new(Mutex::new(reader)),
+            Arc::new(Mutex::new(writer)),
+        ))
+    }
+
+    /// connect over an existing stream, without explicit authorization the xauthority entry for
+    /// the local machine and the display number from $DISPLAY is used
+    pub fn connect_stream(&self, stream: Stream) -> Result<Display, Error> {
+        let display = parse::parse(None).map(|info| info.display).unwrap_or(0);
+
+        self.handshake(stream, &Address::local(), display, 0)
+    }
+
+    fn handshake(
+        &self,
+        stream: Stream,
+        address: &Address,
+        display: u16,
+        screen: usize,
+    ) -> Result<Display, Error> {
+        let auth = match &self.auth {
+            Some(auth) => Some(auth.clone()),
+            None => auth::entry(address, display)?.map(|entry| (entry.name, entry.data)),
+        };
+
+        let mut display =
+            Display::connect_with(stream, auth, self.setup_timeout, self.single_threaded)?;
+
+        display.set_default_screen(self.screen.unwrap_or(screen))?;
+
+        Ok(display)
+    }
+
+    /// connect to the first address the host resolves to that accepts the connection
+    fn open_tcp(&self, host: &str, port: u16) -> Result<(Stream, Address), Error> {
+        let mut connected = None;
+        let mut error = std::io::Error::new(ErrorKind::NotFound, "host has no addresses");
+
+        // a failed lookup is an io error such as a dns failure, not a malformed display
+        for host in (host, port).to_socket_addrs()? {
+            let result = match self.connect_timeout {
+                Some(timeout) => TcpStream::connect_timeout(&host, timeout),
+                None => TcpStream::connect(host),
+            };
+
+            // the error of the last address is reported if none of them accept the connection
+            match result {
+                Ok(stream) => {
+                    connected = Some((stream, host));
+
+                    break;
+                }
+                Err(err) => error = err,
+            }
+        }
+
+        let (tcp_stream, host) = connected.ok_or(error)?;
+
+        tcp_stream.set_nonblocking(false)?;
+
+        let stream = Stream::new(
+            Arc::new(Mutex::new(tcp_stream.try_clone()?)),
+            Arc::new(Mutex::new(tcp_stream)),
+        );
 
-fn open_unix<'a>(path: String) -> Result<Display, Error> {
-    let unix_stream = UnixStream::connect(path).map_err(|_| Error::Stream)?;
+        Ok((stream, Address::from_ip(host.ip())))
+    }
+
+    fn open_unix(&self, path: &str) -> Result<(Stream, Address), Error> {
+        Ok(ConnectOptions::unix_stream(UnixStream::connect(path)?)?)
+    }
+
+    /// connect to a socket in the linux abstract namespace, the name is the path of the socket
+    #[cfg(target_os = "linux")]
+    fn open_abstract(&self, path: &str) -> std::io::Result<(Stream, Address)> {
+        use std::os::linux::net::SocketAddrExt;
+        use std::os::unix::net;
+
+        let unix_stream = UnixStream::connect_addr(&net::SocketAddr::from_abstract_name(path)?)?;
+
+        ConnectOptions::unix_stream(unix_stream)
+    }
+
+    #[cfg(not(target_os = "linux"))]
+    fn open_abstract(&self, _path: &str) -> std::io::Result<(Stream, Address)> {
+        Err(ErrorKind::Unsupported.into())
+    }
+
+    fn unix_stream(unix_stream: UnixStream) -> std::io::Result<(Stream, Address)> {
+        unix_stream.set_nonblocking(false)?;
 
-    unix_stream
-        .set_nonblocking(false)
-        .map_err(|_| Error::Stream)?;
+        let stream = Stream::new(
+            Arc::new(Mutex::new(unix_stream.try_clone()?)),
+            Arc::new(Mutex::new(unix_stream)),
+        );
 
-    Display::connect(Stream::new(
-        Arc::new(Mutex::new(
-            unix_stream.try_clone().map_err(|_| Error::Stream)?,
-        )),
-        Arc::new(Mutex::new(unix_stream)),
-    ))
+        Ok((stream, Address::local()))
+    }
 }
 
 /// Open a connection to the x11 server that controls a display.
@@ -1304,9 +2919,10 @@ fn open_unix<'a>(path: String) -> Result<Display, Error> {
 /// This function is essentialy the yaxi equivalent to [XOpenDisplay](https://tronche.com/gui/x/xlib/display/opening.html).
 ///
 /// On POSIX-conformant systems, the display name or DISPLAY environment variable can be a string in the format:
-/// `hostname:number.screen_number`
+/// `protocol/hostname:number.screen_number`
 ///
-/// - `hostname` - Specifies the name of the host machine on which the display is physically attached. You follow the hostname with either a single colon (:) or a double colon (::).
+/// - `protocol` - Optionally specifies the protocol, either `unix` or `tcp`. Without a protocol a hostname connects over tcp and an empty hostname over a unix socket.
+/// - `hostname` - Specifies the name of the host machine on which the display is physically attached. You follow the hostname with a single colon (:), IPv6 addresses can be written in brackets such as `[::1]:0`. A double colon (::) selects DECnet which is not supported.
 /// - `number` - Specifies the number of the display server on that host machine. You may optionally follow this display number with a period (.). A single CPU can have more than one display. Multiple displays are usually numbered starting with zero.
 /// - `screen_number` - Specifies the screen to be used on that server. Multiple screens can be controlled by a single X server. The screen_number sets an internal variable that can be accessed by using the DefaultScreen() macro or the XDefaultScreen() function if you are using languages other than C (see "Display Macros").
 ///
@@ -1334,18 +2950,400 @@ fn open_unix<'a>(path: String) -> Result<Display, Error> {
 /// }
 /// ```
 pub fn open(display: Option<&str>) -> Result<Display, Error> {
-    let info = parse::parse(display)?;
-
-    match (info.protocol, info.host.is_empty()) {
-        (Protocol::TcpSocket, true) => open_tcp(SocketAddr::from((
-            [127, 0, 0, 1],
-            X_TCP_PORT + info.display,
-        ))),
-        (Protocol::UnixSocket, true) => open_unix(format!("/tmp/.X11-unix/X{}", info.display)),
-        (Protocol::TcpSocket, false) => open_tcp(SocketAddr::from((
-            IpAddr::from_str(&info.host).map_err(|_| Error::InvalidDisplay)?,
-            X_TCP_PORT + info.display,
-        ))),
-        (Protocol::UnixSocket, false) => open_unix(info.host),
+    match display {
+        Some(display) => ConnectOptions::new().display(display).open(),
+        None => ConnectOptions::new().open(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use crate::display::*;
+
+    #[test]
+    fn test_connect_options_auth() -> Result<(), Error> {
+        let (mut server, client) = UnixStream::pair()?;
+
+        let handle = thread::spawn(move || -> std::io::Result<Vec<u8>> {
+            let mut request = [0u8; 12];
+
+            server.read_exact(&mut request)?;
+
+            let mut auth = vec![0u8; 8 + 4];
+
+            server.read_exact(&mut auth)?;
+
+            let reason = b"no";
+
+            server.write_all(&[0, reason.len() as u8, 11, 0, 0, 0, 1, 0])?;
+            server.write_all(&[reason.as_slice(), &[0; 2]].concat())?;
+
+            Ok([request.as_slice(), &auth].concat())
+        });
+
+        let result = ConnectOptions::new()
+            .auth(b"NAME", b"somedata")
+            .connect(client.try_clone()?, client);
+
+        assert!(matches!(result, Err(Error::SetupFailed { reason }) if reason == "no"));
+
+        let request = handle.join().unwrap()?;
+
+        assert_eq!(&request[6..10], &[4, 0, 8, 0]);
+        assert_eq!(&request[12..], b"NAMEsomedata");
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_connect_options_setup_timeout() -> Result<(), Error> {
+        let (_server, client) = UnixStream::pair()?;
+
+        let result = ConnectOptions::new()
+            .auth(b"", b"")
+            .setup_timeout(Duration::from_millis(50))
+            .connect(client.try_clone()?, client);
+
+        assert!(result.is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_open_tcp_lookup_error() {
+        // the .invalid top level domain never resolves, the lookup error is kept as an io error
+        let result = ConnectOptions::new().open_tcp("yaxi.invalid", 6000);
+
+        assert!(matches!(result, Err(Error::Stream { .. })));
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_open_abstract_socket() -> Result<(), Error> {
+        use std::os::linux::net::SocketAddrExt;
+        use std::os::unix::net::{self, UnixListener};
+
+        // nothing exists at this path so the connection can only succeed over the abstract socket
+        let address = net::SocketAddr::from_abstract_name("/tmp/.X11-unix/X47113")?;
+
+        let listener = UnixListener::bind_addr(&address)?;
+
+        let handle = thread::spawn(move || -> std::io::Result<()> {
+            let (mut server, _) = listener.accept()?;
+
+            let mut request = [0u8; 12];
+
+            server.read_exact(&mut request)?;
+            server.write_all(&[0, 2, 11, 0, 0, 0, 1, 0, b'n', b'o', 0, 0])
+        });
+
+        let result = ConnectOptions::new()
+            .display(":47113")
+            .auth(b"", b"")
+            .open();
+
+        assert!(matches!(result, Err(Error::SetupFailed { reason }) if reason == "no"));
+
+        Ok(handle.join().unwrap()?)
+    }
+
+    #[test]
+    #[cfg(target_os = "linux")]
+    fn test_open_unix_ignores_host() -> Result<(), Error> {
+        use std::os::linux::net::SocketAddrExt;
+        use std::os::unix::net::{self, UnixListener};
+
+        let address = net::SocketAddr::from_abstract_name("/tmp/.X11-unix/X47114")?;
+
+        let listener = UnixListener::bind_addr(&address)?;
+
+        let handle = thread::spawn(move || -> std::io::Result<()> {
+            let (mut server, _) = listener.accept()?;
+
+            let mut request = [0u8; 12];
+
+            server.read_exact(&mut request)?;
+            server.write_all(&[0, 2, 11, 0, 0, 0, 1, 0, b'n', b'o', 0, 0])
+        });
+
+        // like libxcb the host is ignored and the socket of the display number is used
+        let result = ConnectOptions::new()
+            .display("unix/somehost:47114")
+            .auth(b"", b"")
+            .open();
+
+        assert!(matches!(result, Err(Error::SetupFailed { reason }) if reason == "no"));
+
+        Ok(handle.join().unwrap()?)
+    }
+
+    #[test]
+    fn test_request_length() -> Result<(), Error> {
+        let (_server, client) = UnixStream::pair()?;
+
+        let client = Arc::new(Mutex::new(client));
+
+        let stream = Stream::new(client.clone(), client);
+
+        assert_eq!(stream.request_length(&[0u8; 8])?, vec![2, 0]);
+        assert!(stream.request_length(&[0u8; 2]).is_err());
+        assert!(stream.request_length(&vec![0u8; 0x40000]).is_err());
+
+        stream.set_max_request_length(0x100000);
+
+        assert_eq!(
+            stream.request_length(&vec![0u8; 0x40000])?,
+            vec![0, 0, 0x01, 0, 0x01, 0]
+        );
+
+        // the maximum of the server applies to requests that fit in the 16 bit length field too
+        stream.set_max_request_length(4);
+
+        assert_eq!(stream.request_length(&[0u8; 16])?, vec![4, 0]);
+        assert!(matches!(
+            stream.request_length(&[0u8; 20]),
+            Err(Error::RequestTooLarge {
+                length: 20,
+                maximum: 16
+            })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_big_requests_enabled_lazily() -> Result<(), Error> {
+        let (mut server, client) = UnixStream::pair()?;
+
+        let stream = Stream::new(
+            Arc::new(Mutex::new(client.try_clone()?)),
+            Arc::new(Mutex::new(client)),
+        );
+
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let signal = Signal::new();
+
+        let replies = Replies::new(stream.clone(), errors.clone(), signal.clone());
+        let events: Queue<Event> = Queue::new(errors, stream.closed(), signal);
+
+        let sequence = SequenceManager::new();
+        let extensions = Arc::new(Mutex::new(HashMap::new()));
+
+        let mut listener = EventListener::new(
+            stream.clone(),
+            events,
+            replies.clone(),
+            sequence.clone(),
+            Roots::new(),
+            extensions.clone(),
+        );
+
+        thread::spawn(move || listener.listen());
+
+        stream.set_big_requests(Some(ExtensionQuery {
+            stream: stream.clone(),
+            replies,
+            sequence: sequence.clone(),
+            extensions,
+        }))?;
+
+        // a request that fits in the 16 bit length field does not query the extension
+        stream.send_void(&sequence, &[0u8; 8])?;
+        stream.flush()?;
+
+        let mut request = [0u8; 8];
+
+        server.read_exact(&mut request)?;
+        assert_eq!(request[2..4], [2, 0]);
+
+        let sender = thread::spawn({
+            let stream = stream.clone();
+            let sequence = sequence.clone();
+
+            move || {
+                stream.send_void(&sequence, &vec![0u8; 0x40000])?;
+                stream.flush()
+            }
+        });
+
+        let mut query = [0u8; 20];
+
+        server.read_exact(&mut query)?;
+        assert_eq!(query[0], Opcode::QUERY_EXTENSION);
+        assert_eq!(&query[8..], b"BIG-REQUESTS");
+
+        let mut reply = [0u8; 32];
+
+        reply[0] = Response::REPLY;
+        reply[2] = 2;
+        reply[8] = 1;
+        reply[9] = 133;
+
+        server.write_all(&reply)?;
+
+        let mut enable = [0u8; 4];
+
+        server.read_exact(&mut enable)?;
+        assert_eq!(enable, [133, 0, 1, 0]);
+
+        let mut reply = [0u8; 32];
+
+        reply[0] = Response::REPLY;
+        reply[2] = 3;
+        reply[8..12].copy_from_slice(&0x100000u32.to_le_bytes());
+
+        server.write_all(&reply)?;
+
+        let mut request = vec![0u8; 0x40000 + 4];
+
+        server.read_exact(&mut request)?;
+        assert_eq!(request[..8], [0, 0, 0, 0, 0x01, 0, 0x01, 0]);
+
+        sender.join().unwrap()?;
+
+        assert_eq!(stream.max_request_length(), 0x100000);
+
+        // requests over the new maximum are rejected without asking the server again
+        assert!(matches!(
+            stream.send_void(&sequence, &vec![0u8; 0x400000]),
+            Err(Error::RequestTooLarge { .. })
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_reader_splits_responses() -> Result<(), Error> {
+        let (mut server, client) = UnixStream::pair()?;
+
+        let client = Arc::new(Mutex::new(client));
+
+        let mut reader = Reader::new(Stream::new(client.clone(), client));
+
+        let mut reply = vec![0u8; 40];
+
+        reply[0] = Response::REPLY;
+        reply[4..8].copy_from_slice(&2u32.to_le_bytes());
+        reply[32..40].copy_from_slice(b"yaxi-byt");
+
+        let mut event = vec![0u8; 32];
+
+        event[0] = Response::MAP_NOTIFY;
+
+        server.write_all(&[event.clone(), reply, event].concat())?;
+
+        reader.next_response()?;
+
+        let generic: GenericEvent = reader.recv_decode()?;
+        assert_eq!(generic.opcode, Response::MAP_NOTIFY);
+
+        reader.next_response()?;
+
+        let generic: GenericEvent = reader.recv_decode()?;
+        assert_eq!(generic.opcode, Response::REPLY);
+
+        reader.recv(28)?;
+        assert_eq!(reader.recv(8)?, b"yaxi-byt".to_vec());
+        assert!(reader.recv(1).is_err());
+
+        reader.next_response()?;
+
+        let generic: GenericEvent = reader.recv_decode()?;
+        assert_eq!(generic.opcode, Response::MAP_NOTIFY);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_inline_listener() -> Result<(), Error> {
+        let (mut server, client) = UnixStream::pair()?;
+
+        let stream = Stream::new(
+            Arc::new(Mutex::new(client.try_clone()?)),
+            Arc::new(Mutex::new(client)),
+        );
+
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let signal = Signal::new();
+
+        let replies = Replies::new(stream.clone(), errors.clone(), signal.clone());
+        let events: Queue<Event> = Queue::new(errors, stream.closed(), signal.clone());
+
+        let on_disconnect: Arc<Mutex<Option<DisconnectCallback>>> = Arc::new(Mutex::new(None));
+
+        let (sender, receiver) = std::sync::mpsc::channel();
+
+        *lock!(on_disconnect)? = Some(Box::new(move |error| {
+            let _ = sender.send(error.to_string());
+        }));
+
+        let listener = EventListener::new(
+            stream.clone(),
+            events.clone(),
+            replies.clone(),
+            SequenceManager::new(),
+            Roots::new(),
+            Arc::new(Mutex::new(HashMap::new())),
+        );
+
+        signal.set_driver(Some(Arc::new(InlineListener {
+            listener: Mutex::new(listener),
+            stream: stream.clone(),
+            events: events.clone(),
+            replies,
+            signal: signal.clone(),
+            on_disconnect,
+        })))?;
+
+        // nothing is read until the queue is asked for events
+        assert!(events.try_pop()?.is_none());
+        assert!(events.wait_timeout(Duration::from_millis(10))?.is_none());
+
+        let mut event = vec![0u8; 32];
+
+        event[0] = Response::MAP_NOTIFY;
+
+        server.write_all(&[event.clone(), event.clone()].concat())?;
+
+        assert!(matches!(events.try_pop()?, Some(Event::MapNotify { .. })));
+        assert!(matches!(events.wait()?, Event::MapNotify { .. }));
+        assert!(events.try_pop()?.is_none());
+
+        // waiting for activity reads the pending event instead of deadlocking on the signal
+        server.write_all(&event)?;
+
+        let (ready_sender, ready) = std::sync::mpsc::channel();
+
+        thread::spawn({
+            let events = events.clone();
+
+            move || ready_sender.send(events.wait_ready(None))
+        });
+
+        assert!(ready
+            .recv_timeout(Duration::from_secs(2))
+            .map_err(|_| Error::FailedToWait)??);
+        assert!(matches!(events.try_pop()?, Some(Event::MapNotify { .. })));
+
+        drop(server);
+
+        assert!(matches!(events.wait(), Err(Error::ConnectionClosed)));
+        assert!(stream.is_closed());
+        assert!(receiver.recv_timeout(Duration::from_secs(1)).is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_predefined_atoms() {
+        assert_eq!(
+            Atom::predefined("WM_TRANSIENT_FOR"),
+            Some(Atom::WM_TRANSIENT_FOR)
+        );
+        assert_eq!(Atom::predefined("_NET_WM_NAME"), None);
+        assert_eq!(Atom::WINDOW.predefined_name(), Some("WINDOW"));
+        assert_eq!(Atom::STRING.id(), 31);
+        assert_eq!(Atom::new(0).predefined_name(), None);
+        assert_eq!(Atom::new(69).predefined_name(), None);
     }
 }
@@ -1,10 +1,9 @@
 use crate::display::error::*;
 
 use std::env;
-use std::iter::Peekable;
-use std::str::Chars;
+use std::net::Ipv6Addr;
 
-// TODO: currently cant find the specification for DISPLAY so this might not be 100% accurate
+// the format is described in the X(7) manual page and implemented by libxcb in xcb_util.c
 
 /// represents which protocol the x11 connection should use
 
@@ -19,15 +18,21 @@ pub enum Protocol {
 impl Protocol {
     pub fn from(value: String) -> Result<Protocol, Error> {
         match value.as_str().to_lowercase().trim_end_matches(":") {
-            "unix" => Ok(Protocol::UnixSocket),
-            "tcp" => Ok(Protocol::TcpSocket),
+            "unix" | "local" => Ok(Protocol::UnixSocket),
+            "tcp" | "inet" | "inet6" => Ok(Protocol::TcpSocket),
             _ => Err(Error::InvalidProtocol { protocol: value }),
         }
     }
+
+    fn is_protocol(value: &str) -> bool {
+        Protocol::from(value.to_string()).is_ok()
+    }
 }
 
 /// representing the $DISPLAY environment variable
-/// syntax: <host>/<protocol>:<display>.<screen>
+/// syntax: [<protocol>/]<host>:<display>.<screen>, <host>/<protocol>:<display>.<screen> or
+/// unix:<path>, the host can be a hostname, an ipv4 address or an ipv6 address with or without
+/// brackets
 
 #[derive(Debug, Clone, Default, PartialEq)]
 pub struct DisplayInfo {
@@ -48,138 +53,120 @@ impl DisplayInfo {
     }
 }
 
-#[derive(PartialEq)]
-pub enum State {
-    Host,
-    Protocol,
-    Display,
-    Screen,
-    Unix,
-    Finished,
-}
-
-pub struct Iter<'a> {
-    chars: Peekable<Chars<'a>>,
+/// this parses the $DISPLAY environment variable
+pub struct Parser<'a> {
+    display: &'a str,
 }
 
-impl<'a> Iter<'a> {
-    pub fn new(chars: Peekable<Chars<'a>>) -> Iter {
-        Iter { chars }
+impl<'a> Parser<'a> {
+    pub fn new(display: &'a str) -> Parser<'a> {
+        Parser { display }
     }
 
-    pub fn take_while<F: Fn(&char) -> bool>(&mut self, f: F) -> Result<Vec<char>, Error> {
-        let mut buf: Vec<char> = Vec::new();
+    /// parse the display and screen number after the last colon
+    fn numbers(numbers: &str) -> Result<(u16, u16), Error> {
+        let (display, screen) = numbers.split_once('.').unwrap_or((numbers, "0"));
 
-        while self.chars.peek().map(|c| f(c)).unwrap_or(false) {
-            buf.push(self.chars.next().ok_or(Error::InvalidDisplay)?);
-        }
+        let parse = |number: &str| {
+            number
+                .chars()
+                .all(|c| c.is_ascii_digit())
+                .then(|| number.parse::<u16>().ok())
+                .flatten()
+                .ok_or(Error::InvalidDisplay)
+        };
 
-        Ok(buf)
+        Ok((parse(display)?, parse(screen)?))
     }
 
-    pub fn next_option(&mut self) -> Option<char> {
-        self.chars.next()
-    }
+    pub fn parse(&mut self) -> Result<DisplayInfo, Error> {
+        // a full path to the socket
+        if let Some(path) = self
+            .display
+            .strip_prefix("unix:")
+            .filter(|path| path.starts_with('/'))
+        {
+            return Ok(DisplayInfo::new(
+                path.to_string(),
+                Protocol::UnixSocket,
+                0,
+                0,
+            ));
+        }
 
-    pub fn next(&mut self) -> Result<char, Error> {
-        self.chars.next().ok_or(Error::InvalidDisplay)
-    }
+        let mut protocol = None;
+        let mut rest = self.display;
 
-    pub fn expect(&mut self, expect: char) -> Result<(), Error> {
-        self.next().and_then(|c| {
-            (c != expect)
-                .then(|| Err(Error::InvalidDisplay))
-                .unwrap_or(Ok(()))
-        })
-    }
-}
+        if let Some((before, after)) = rest.split_once('/') {
+            let (host, after) = after.split_once(':').ok_or(Error::InvalidDisplay)?;
 
-/// this parses the $DISPLAY environment variable using a state machine
-pub struct Parser<'a> {
-    iter: Iter<'a>,
-    display: DisplayInfo,
-    state: State,
-}
+            if Protocol::is_protocol(before) {
+                // protocol/host:display
+                protocol = Some(Protocol::from(before.to_string())?);
+                rest = &rest[before.len() + 1..];
+            } else {
+                // host/protocol:display
+                protocol = Some(Protocol::from(host.to_string())?);
 
-impl<'a> Parser<'a> {
-    pub fn new(display: &'a str) -> Parser<'a> {
-        Parser {
-            iter: Iter::new(display.chars().peekable()),
-            display: DisplayInfo::default(),
-            state: State::Host,
+                return self.finish(before, protocol, after);
+            }
         }
+
+        let colon = rest.rfind(':').ok_or(Error::InvalidDisplay)?;
+
+        self.finish(&rest[..colon], protocol, &rest[colon + 1..])
     }
 
-    pub fn parse(&mut self) -> Result<DisplayInfo, Error> {
-        while self.state != State::Finished {
-            match self.state {
-                State::Host => {
-                    self.display.host = self
-                        .iter
-                        .take_while(|c| *c != ':' && *c != '/')?
-                        .iter()
-                        .collect();
-
-                    // TODO: double colon (:) support
-
-                    match (self.display.host.as_str(), self.iter.next()?) {
-                        ("unix", _) => self.state = State::Unix,
-                        (_, ':') => self.state = State::Display,
-                        (_, '/') => self.state = State::Protocol,
-                        _ => unreachable!(),
-                    }
-                }
-                State::Protocol => {
-                    self.display.protocol =
-                        Protocol::from(self.iter.take_while(|c| *c != ':')?.iter().collect())?;
-
-                    self.iter.next()?;
-
-                    self.state = State::Display;
-                }
-                State::Display => {
-                    self.display.display = self
-                        .iter
-                        .take_while(|c| *c != '.')?
-                        .iter()
-                        .collect::<String>()
-                        .parse::<u16>()
-                        .map_err(|_| Error::InvalidDisplay)?;
-
-                    match self.iter.next_option() {
-                        Some('.') => self.state = State::Screen,
-                        _ => self.state = State::Finished,
-                    }
-                }
-                State::Screen => {
-                    self.display.screen = self
-                        .iter
-                        .take_while(|c| *c != '.')?
-                        .iter()
-                        .collect::<String>()
-                        .parse::<u16>()
-                        .map_err(|_| Error::InvalidDisplay)?;
-
-                    self.state = State::Finished;
-                }
-                State::Unix => {
-                    self.display.host = self.iter.take_while(|_| true)?.iter().collect();
-
-                    self.state = State::Finished;
-                }
-                _ => {}
+    fn finish(
+        &self,
+        host: &str,
+        protocol: Option<Protocol>,
+        numbers: &str,
+    ) -> Result<DisplayInfo, Error> {
+        let (display, screen) = Parser::numbers(numbers)?;
+
+        let host = match host
+            .strip_prefix('[')
+            .and_then(|host| host.strip_suffix(']'))
+        {
+            Some(host) => host
+                .parse::<Ipv6Addr>()
+                .map_err(|_| Error::InvalidDisplay)?
+                .to_string(),
+            None if host.starts_with('[') => return Err(Error::InvalidDisplay),
+            // a double colon selects decnet unless the host is an ipv6 address such as ::1
+            None if host.ends_with(':') && host.parse::<Ipv6Addr>().is_err() => {
+                return Err(Error::InvalidProtocol {
+                    protocol: String::from("decnet"),
+                })
             }
-        }
-
-        Ok(self.display.clone())
+            None => host.to_string(),
+        };
+
+        let protocol = match (protocol, host.as_str()) {
+            (_, "unix") => Protocol::UnixSocket,
+            (Some(protocol), _) => protocol,
+            (None, "") => Protocol::UnixSocket,
+            (None, _) => Protocol::TcpSocket,
+        };
+
+        // like libxcb a unix connection ignores the host, only unix:<path> names a socket
+        let host = if protocol == Protocol::UnixSocket {
+            String::new()
+        } else {
+            host
+        };
+
+        Ok(DisplayInfo::new(host, protocol, display, screen))
     }
 }
 
 /// parse the DISPLAY env or provided string
 pub fn parse<'a>(display: Option<&'a str>) -> Result<DisplayInfo, Error> {
-    let env = env::var("DISPLAY").map_err(|_| Error::InvalidDisplay)?;
-
-    Parser::new(display.unwrap_or(&env)).parse()
+    match display {
+        Some(display) => Parser::new(display).parse(),
+        None => Parser::new(&env::var("DISPLAY").map_err(|_| Error::InvalidDisplay)?).parse(),
+    }
 }
 
 #[cfg(test)]
@@ -221,4 +208,80 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_invalid() {
+        assert!(parse::parse(Some("localhost")).is_err());
+        assert!(parse::parse(Some("localhost/tcp")).is_err());
+        assert!(parse::parse(Some(":abc")).is_err());
+    }
+
+    #[test]
+    fn test_hostname() -> Result<(), Error> {
+        assert_eq!(
+            parse::parse(Some("localhost:10.1"))?,
+            DisplayInfo::new(String::from("localhost"), Protocol::TcpSocket, 10, 1)
+        );
+
+        assert_eq!(
+            parse::parse(Some("tcp/example.org:0"))?,
+            DisplayInfo::new(String::from("example.org"), Protocol::TcpSocket, 0, 0)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_ipv6() -> Result<(), Error> {
+        let localhost = DisplayInfo::new(String::from("::1"), Protocol::TcpSocket, 0, 0);
+
+        assert_eq!(parse::parse(Some("[::1]:0"))?, localhost);
+        assert_eq!(parse::parse(Some("::1:0"))?, localhost);
+        assert_eq!(parse::parse(Some("inet6/[::1]:0"))?, localhost);
+
+        assert_eq!(
+            parse::parse(Some(":::0"))?,
+            DisplayInfo::new(String::from("::"), Protocol::TcpSocket, 0, 0)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_prefix() -> Result<(), Error> {
+        assert_eq!(
+            parse::parse(Some("unix:0"))?,
+            DisplayInfo::new(String::new(), Protocol::UnixSocket, 0, 0)
+        );
+
+        assert_eq!(
+            parse::parse(Some("unix/:1.2"))?,
+            DisplayInfo::new(String::new(), Protocol::UnixSocket, 1, 2)
+        );
+
+        assert_eq!(
+            parse::parse(Some("unix/somehost:0"))?,
+            DisplayInfo::new(String::new(), Protocol::UnixSocket, 0, 0)
+        );
+
+        assert_eq!(
+            parse::parse(Some("somehost/local:4"))?,
+            DisplayInfo::new(String::new(), Protocol::UnixSocket, 4, 0)
+        );
+
+        assert_eq!(
+            parse::parse(Some("tcp/:3"))?,
+            DisplayInfo::new(String::new(), Protocol::TcpSocket, 3, 0)
+        );
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_host() {
+        assert!(parse::parse(Some("host::0")).is_err());
+        assert!(parse::parse(Some("[::1:0")).is_err());
+        assert!(parse::parse(Some("host:0.")).is_err());
+        assert!(parse::parse(Some("quic/host:0")).is_err());
+    }
 }
@@ -1,8 +1,12 @@
 use super::*;
 
+use crate::extension::xc_misc::XcMisc;
+use crate::extension::Extension;
+
 use std::sync::Mutex;
 
-static XID: Mutex<Xid> = Mutex::new(Xid::new());
+/// the amount of ids requested at once when the server has no contiguous range left
+const XID_LIST_COUNT: u32 = 64;
 
 macro_rules! lock {
     ($mutex:expr) => {
@@ -10,41 +14,260 @@ macro_rules! lock {
     };
 }
 
-pub struct Xid {
+struct Xid {
     base: u32,
     mask: u32,
+    inc: u32,
     next: u32,
+    max: u32,
+    free: Vec<u32>,
+    xc_misc: Option<XcMisc>,
+    query: Option<ExtensionQuery>,
 }
 
 impl Xid {
-    const fn new() -> Xid {
+    fn new(base: u32, mask: u32) -> Xid {
+        // ids are allocated in steps of the lowest bit of the mask
+        let inc = mask & mask.wrapping_neg();
+
         Xid {
-            base: 0,
-            mask: 69,
-            next: 420,
+            base,
+            mask,
+            inc,
+            next: inc,
+            max: mask,
+            free: Vec::new(),
+            xc_misc: None,
+            query: None,
         }
     }
 
     fn next(&mut self) -> Result<u32, Error> {
-        self.next += 1;
+        if let Some(id) = self.free.pop() {
+            return Ok(id);
+        }
+
+        if self.inc == 0 || self.next > self.max {
+            self.refill()?;
+        }
+
+        if let Some(id) = self.free.pop() {
+            return Ok(id);
+        }
+
+        let id = self.next;
+
+        // the mask never has the top 3 bits set so this can not overflow
+        self.next += self.inc;
 
-        if self.next >= self.mask {
+        Ok(id | self.base)
+    }
+
+    /// ask the server for ids that are no longer in use, xc-misc is queried the first time the ids
+    /// run out
+    fn refill(&mut self) -> Result<(), Error> {
+        if let Some(query) = self.query.take() {
+            let extension = query.query(Extension::XcMisc)?;
+
+            if extension.present != 0 {
+                self.xc_misc = Some(XcMisc::new(
+                    query.stream,
+                    query.replies,
+                    query.sequence,
+                    extension.major_opcode,
+                ));
+            }
+        }
+
+        let xc_misc = self.xc_misc.as_ref().ok_or(Error::RanOutOfXid)?;
+
+        let (start, count) = xc_misc.get_xid_range()?;
+
+        // a range of a single id 0 means the server has no range left
+        if count > 0 && !(start == 0 && count == 1) {
+            self.next = start & self.mask;
+            self.max = (count - 1)
+                .saturating_mul(self.inc)
+                .saturating_add(self.next)
+                .min(self.mask);
+
+            return Ok(());
+        }
+
+        self.free = xc_misc.get_xid_list(XID_LIST_COUNT)?;
+
+        if self.free.is_empty() {
             Err(Error::RanOutOfXid)
         } else {
-            Ok(self.next | self.base)
+            Ok(())
         }
     }
 }
 
-pub fn setup(base: u32, mask: u32) -> Result<(), Error> {
-    let mut lock = lock!(XID)?;
+/// a resource id allocator for a single connection, once the ids assigned during setup run out
+/// ids that were freed are reused through the XC-MISC extension if the server supports it
+#[derive(Clone)]
+pub struct XidAllocator {
+    xid: Arc<Mutex<Xid>>,
+}
 
-    lock.base = base;
-    lock.mask = mask;
+impl XidAllocator {
+    pub(crate) fn new() -> XidAllocator {
+        XidAllocator {
+            xid: Arc::new(Mutex::new(Xid::new(0, 0))),
+        }
+    }
+
+    pub(crate) fn setup(&self, base: u32, mask: u32) -> Result<(), Error> {
+        let mut xid = lock!(self.xid)?;
+
+        *xid = Xid::new(base, mask);
+
+        Ok(())
+    }
 
-    Ok(())
+    /// set how xc-misc is queried once the ids assigned during setup run out
+    pub(crate) fn set_xc_misc(&self, query: ExtensionQuery) -> Result<(), Error> {
+        lock!(self.xid)?.query = Some(query);
+
+        Ok(())
+    }
+
+    /// allocate a new resource id
+    pub fn next(&self) -> Result<u32, Error> {
+        lock!(self.xid)?.next()
+    }
 }
 
-pub fn next() -> Result<u32, Error> {
-    lock!(XID)?.next()
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::extension::xc_misc::MinorOpcode;
+
+    use std::collections::HashMap;
+    use std::io::{Read, Write};
+    use std::os::unix::net::UnixStream;
+
+    #[test]
+    fn test_xid_allocation() -> Result<(), Error> {
+        let xid = XidAllocator::new();
+
+        assert!(xid.next().is_err());
+
+        xid.setup(0x0400_0000, 0x0000_0070)?;
+
+        let ids = (0..7)
+            .map(|_| xid.next())
+            .collect::<Result<Vec<u32>, Error>>()?;
+
+        assert_eq!(
+            ids,
+            vec![
+                0x0400_0010,
+                0x0400_0020,
+                0x0400_0030,
+                0x0400_0040,
+                0x0400_0050,
+                0x0400_0060,
+                0x0400_0070
+            ]
+        );
+
+        // without xc-misc there is no way to find ids that were freed
+        assert!(xid.next().is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_xc_misc_queried_when_ids_run_out() -> Result<(), Error> {
+        let (mut server, client) = UnixStream::pair()?;
+
+        let stream = Stream::new(
+            Arc::new(Mutex::new(client.try_clone()?)),
+            Arc::new(Mutex::new(client)),
+        );
+
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let signal = Signal::new();
+
+        let replies = Replies::new(stream.clone(), errors.clone(), signal.clone());
+        let events: Queue<Event> = Queue::new(errors, stream.closed(), signal);
+
+        let sequence = SequenceManager::new();
+        let extensions = Arc::new(Mutex::new(HashMap::new()));
+
+        let mut listener = EventListener::new(
+            stream.clone(),
+            events,
+            replies.clone(),
+            sequence.clone(),
+            Roots::new(),
+            extensions.clone(),
+        );
+
+        std::thread::spawn(move || listener.listen());
+
+        let xid = XidAllocator::new();
+
+        xid.setup(0x0400_0000, 0x0000_0030)?;
+        xid.set_xc_misc(ExtensionQuery {
+            stream,
+            replies,
+            sequence,
+            extensions,
+        })?;
+
+        // the ids from the setup are used before the server is asked for anything
+        for id in [0x0400_0010, 0x0400_0020, 0x0400_0030] {
+            assert_eq!(xid.next()?, id);
+        }
+
+        let allocator = std::thread::spawn({
+            let xid = xid.clone();
+
+            move || (xid.next(), xid.next())
+        });
+
+        let mut query = [0u8; 16];
+
+        server.read_exact(&mut query)?;
+        assert_eq!(query[0], Opcode::QUERY_EXTENSION);
+        assert_eq!(&query[8..15], b"XC-MISC");
+
+        let mut reply = [0u8; 32];
+
+        reply[0] = Response::REPLY;
+        reply[2] = 1;
+        reply[8] = 1;
+        reply[9] = 140;
+
+        server.write_all(&reply)?;
+
+        // the extension is only queried once, later refills ask for a range right away
+        for sequence in [2u8, 3] {
+            let mut request = [0u8; 4];
+
+            server.read_exact(&mut request)?;
+            assert_eq!(request, [140, MinorOpcode::GET_XID_RANGE, 1, 0]);
+
+            let mut reply = [0u8; 32];
+
+            reply[0] = Response::REPLY;
+            reply[2] = sequence;
+            reply[8..12]
+                .copy_from_slice(&(0x0400_0000 | (sequence as u32 - 1) * 0x10).to_le_bytes());
+            reply[12..16].copy_from_slice(&1u32.to_le_bytes());
+
+            server.write_all(&reply)?;
+        }
+
+        let (first, second) = allocator.join().unwrap();
+
+        assert_eq!(first?, 0x0400_0010);
+        assert_eq!(second?, 0x0400_0020);
+
+        Ok(())
+    }
 }
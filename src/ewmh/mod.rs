@@ -1,5 +1,6 @@
 use crate::display::error::Error;
 use crate::display::{Atom, Display};
+use crate::window::property::{self, FixedPropertyValue, PropertyValue};
 use crate::window::{PropFormat, PropMode, Window};
 
 use std::collections::HashMap;
@@ -37,6 +38,28 @@ impl DesktopGeometry {
     }
 }
 
+impl PropertyValue for DesktopGeometry {
+    const FORMAT: PropFormat = PropFormat::Format32;
+
+    fn encode(&self, type_: Atom, data: &mut Vec<u8>) -> Result<(), Error> {
+        self.width.encode(type_, data)?;
+        self.height.encode(type_, data)
+    }
+
+    fn decode(type_: Atom, data: &[u8]) -> Result<Self, Error> {
+        let data = property::decode_exact::<8>(data)?;
+
+        Ok(DesktopGeometry::new(
+            u32::decode(type_, &data[..4])?,
+            u32::decode(type_, &data[4..])?,
+        ))
+    }
+}
+
+impl FixedPropertyValue for DesktopGeometry {
+    const SIZE: usize = 8;
+}
+
 /// this represents the window viewport x and y coordinates, <https://specifications.freedesktop.org/wm-spec/1.3/ar01s03.html#id-1.4.7>
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
@@ -54,6 +77,49 @@ impl DesktopViewport {
     }
 }
 
+impl PropertyValue for DesktopViewport {
+    const FORMAT: PropFormat = PropFormat::Format32;
+
+    fn encode(&self, type_: Atom, data: &mut Vec<u8>) -> Result<(), Error> {
+        self.x.encode(type_, data)?;
+        self.y.encode(type_, data)
+    }
+
+    fn decode(type_: Atom, data: &[u8]) -> Result<Self, Error> {
+        let data = property::decode_exact::<8>(data)?;
+
+        Ok(DesktopViewport::new(
+            u32::decode(type_, &data[..4])?,
+            u32::decode(type_, &data[4..])?,
+        ))
+    }
+}
+
+impl FixedPropertyValue for DesktopViewport {
+    const SIZE: usize = 8;
+}
+
+crate::atoms! {
+    struct DesktopNamesAtoms {
+        desktop_names: "_NET_DESKTOP_NAMES",
+        utf8_string: "UTF8_STRING",
+    }
+}
+
+crate::atoms! {
+    struct WindowTypeAtoms {
+        window_type: "_NET_WM_WINDOW_TYPE",
+        desktop: "_NET_WM_WINDOW_TYPE_DESKTOP",
+        dock: "_NET_WM_WINDOW_TYPE_DOCK",
+        toolbar: "_NET_WM_WINDOW_TYPE_TOOLBAR",
+        menu: "_NET_WM_WINDOW_TYPE_MENU",
+        utility: "_NET_WM_WINDOW_TYPE_UTILITY",
+        splash: "_NET_WM_WINDOW_TYPE_SPLASH",
+        dialog: "_NET_WM_WINDOW_TYPE_DIALOG",
+        normal: "_NET_WM_WINDOW_TYPE_NORMAL",
+    }
+}
+
 /// Ewmh is a thin wrapper for window allowing the user to implement ewmh compliant applications in
 /// a simple maner
 pub struct Ewmh {
@@ -66,22 +132,18 @@ impl Ewmh {
     pub fn set_supported(&self, atoms: &[Atom]) -> Result<(), Error> {
         let atom = self.display.intern_atom("_NET_SUPPORTED", false)?;
 
-        let values = atoms.iter().map(|atom| atom.id()).collect::<Vec<u32>>();
-
-        self.set_u32_list_property(atom, Atom::ATOM, PropFormat::Format32, &values)
+        self.window.set_property(atom, Atom::ATOM, &atoms.to_vec())
     }
 
     /// get the supported ewmh hints, (wrapper for _NET_SUPPORTED)
     pub fn get_supported(&self) -> Result<Option<Vec<Atom>>, Error> {
         let atom = self.display.intern_atom("_NET_SUPPORTED", false)?;
 
-        self.get_u32_list_property(atom, Atom::ATOM)
-            .and_then(|ret| {
-                let atoms =
-                    ret.map(|atoms| atoms.iter().map(|id| Atom::new(*id)).collect::<Vec<Atom>>());
+        let atoms = self
+            .get_u32_list_property(atom, Atom::ATOM)?
+            .map(|atoms| atoms.into_iter().map(Atom::new).collect());
 
-                Ok(atoms)
-            })
+        Ok(atoms)
     }
 
     /// get the current active window, (wrapper for _NET_ACTIVE_WINDOW)
@@ -104,15 +166,15 @@ impl Ewmh {
     pub fn set_client_list(&self, clients: &[u32]) -> Result<(), Error> {
         let atom = self.display.intern_atom("_NET_CLIENT_LIST", false)?;
 
-        self.set_u32_list_property(atom, Atom::WINDOW, PropFormat::Format32, clients)
+        self.window
+            .set_property(atom, Atom::WINDOW, &clients.to_vec())
     }
 
     /// get the names of virtual desktops, (wrapper for _NET_DESKTOP_NAMES)
     pub fn get_desktop_names(&self) -> Result<Option<Vec<Result<String, FromUtf8Error>>>, Error> {
-        let atom = self.display.intern_atom("_NET_DESKTOP_NAMES", false)?;
-        let utf8 = self.display.intern_atom("UTF8_STRING", false)?;
+        let atoms = DesktopNamesAtoms::new(&self.display)?;
 
-        self.map_property(atom, utf8, |data, _| {
+        self.map_property(atoms.desktop_names, atoms.utf8_string, |data, _| {
             data.split(|character| *character == 0)
                 .map(|desktop| String::from_utf8(desktop.to_vec()))
                 .collect::<Vec<Result<String, FromUtf8Error>>>()
@@ -121,16 +183,20 @@ impl Ewmh {
 
     /// set the names of virtual desktops, (wrapper for _NET_DESKTOP_NAMES)
     pub fn set_desktop_names(&self, desktops: &[String]) -> Result<(), Error> {
-        let atom = self.display.intern_atom("_NET_DESKTOP_NAMES", false)?;
-        let utf8 = self.display.intern_atom("UTF8_STRING", false)?;
+        let atoms = DesktopNamesAtoms::new(&self.display)?;
 
         let bytes = desktops
             .iter()
             .flat_map(|desktop| [desktop.as_bytes(), &[0]].concat())
             .collect::<Vec<u8>>();
 
-        self.window
-            .change_property(atom, utf8, PropFormat::Format8, PropMode::Replace, &bytes)
+        self.window.change_property(
+            atoms.desktop_names,
+            atoms.utf8_string,
+            PropFormat::Format8,
+            PropMode::Replace,
+            &bytes,
+        )
     }
 
     /// get the stacked client list, this list only contains the windows managed by a ewmh compliant window
@@ -154,52 +220,30 @@ impl Ewmh {
     pub fn set_current_desktop(&self, desktop: u32) -> Result<(), Error> {
         let atom = self.display.intern_atom("_NET_CURRENT_DESKTOP", false)?;
 
-        self.window.change_property(
-            atom,
-            Atom::CARDINAL,
-            PropFormat::Format32,
-            PropMode::Replace,
-            &desktop.to_le_bytes(),
-        )
+        self.window.set_property(atom, Atom::CARDINAL, &desktop)
     }
 
     /// get the desktop viewport, (wrapper for _NET_DESKTOP_VIEWPORT)
     pub fn get_desktop_viewport(&self) -> Result<Option<Vec<DesktopViewport>>, Error> {
         let atom = self.display.intern_atom("_NET_DESKTOP_VIEWPORT", false)?;
 
-        self.map_property(atom, Atom::CARDINAL, |data, _| {
-            data.chunks(8)
-                .filter(|chunk| chunk.len() == 8)
-                .map(|chunk| DesktopViewport {
-                    x: u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]),
-                    y: u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]),
-                })
-                .collect::<Vec<DesktopViewport>>()
-        })
+        let viewport = self
+            .get_u32_list_property(atom, Atom::CARDINAL)?
+            .map(|data| {
+                data.chunks_exact(2)
+                    .map(|chunk| DesktopViewport::new(chunk[0], chunk[1]))
+                    .collect()
+            });
+
+        Ok(viewport)
     }
 
     /// set the desktop viewport, (wrapper for _NET_DESKTOP_VIEWPORT)
     pub fn set_desktop_viewport(&self, viewport: &[DesktopViewport]) -> Result<(), Error> {
         let atom = self.display.intern_atom("_NET_DESKTOP_VIEWPORT", false)?;
 
-        let data = viewport
-            .iter()
-            .flat_map(|desktop| {
-                [
-                    desktop.x.to_le_bytes().to_vec(),
-                    desktop.y.to_le_bytes().to_vec(),
-                ]
-            })
-            .flatten()
-            .collect::<Vec<u8>>();
-
-        self.window.change_property(
-            atom,
-            Atom::CARDINAL,
-            PropFormat::Format32,
-            PropMode::Replace,
-            &data,
-        )
+        self.window
+            .set_property(atom, Atom::CARDINAL, &viewport.to_vec())
     }
 
     /// get the desktop geometry, width and height, (wrapper for _NET_DESKTOP_GEOMETRY)
@@ -211,10 +255,7 @@ impl Ewmh {
             .map(|mut data| {
                 data.resize(2, 0);
 
-                DesktopGeometry {
-                    width: data[0],
-                    height: data[1],
-                }
+                DesktopGeometry::new(data[0], data[1])
             });
 
         Ok(geometry)
@@ -227,13 +268,7 @@ impl Ewmh {
             .display
             .intern_atom("_NET_SUPPORTING_WM_CHECK", false)?;
 
-        self.window.change_property(
-            atom,
-            Atom::WINDOW,
-            PropFormat::Format32,
-            PropMode::Replace,
-            &wid.to_le_bytes(),
-        )
+        self.window.set_property(atom, Atom::WINDOW, &wid)
     }
 
     /// The Client SHOULD set this to the title of the window in UTF-8 encoding.
@@ -253,56 +288,26 @@ impl Ewmh {
 
     /// get the window type, (wrapper for _NET_WM_WINDOW_TYPE)
     pub fn get_wm_window_type(&self) -> Result<Vec<EwmhWindowType>, Error> {
-        let atom = self.display.intern_atom("_NET_WM_WINDOW_TYPE", false)?;
+        let atoms = WindowTypeAtoms::new(&self.display)?;
 
         let map = HashMap::from([
-            (
-                self.display
-                    .intern_atom("_NET_WM_WINDOW_TYPE_DESKTOP", false)?,
-                EwmhWindowType::Desktop,
-            ),
-            (
-                self.display
-                    .intern_atom("_NET_WM_WINDOW_TYPE_DOCK", false)?,
-                EwmhWindowType::Dock,
-            ),
-            (
-                self.display
-                    .intern_atom("_NET_WM_WINDOW_TYPE_TOOLBAR", false)?,
-                EwmhWindowType::Toolbar,
-            ),
-            (
-                self.display
-                    .intern_atom("_NET_WM_WINDOW_TYPE_MENU", false)?,
-                EwmhWindowType::Menu,
-            ),
-            (
-                self.display
-                    .intern_atom("_NET_WM_WINDOW_TYPE_UTILITY", false)?,
-                EwmhWindowType::Utility,
-            ),
-            (
-                self.display
-                    .intern_atom("_NET_WM_WINDOW_TYPE_SPLASH", false)?,
-                EwmhWindowType::Splash,
-            ),
-            (
-                self.display
-                    .intern_atom("_NET_WM_WINDOW_TYPE_DIALOG", false)?,
-                EwmhWindowType::Dialog,
-            ),
-            (
-                self.display
-                    .intern_atom("_NET_WM_WINDOW_TYPE_NORMAL", false)?,
-                EwmhWindowType::Normal,
-            ),
+            (atoms.desktop, EwmhWindowType::Desktop),
+            (atoms.dock, EwmhWindowType::Dock),
+            (atoms.toolbar, EwmhWindowType::Toolbar),
+            (atoms.menu, EwmhWindowType::Menu),
+            (atoms.utility, EwmhWindowType::Utility),
+            (atoms.splash, EwmhWindowType::Splash),
+            (atoms.dialog, EwmhWindowType::Dialog),
+            (atoms.normal, EwmhWindowType::Normal),
         ]);
 
-        let type_ = self.get_u32_list_property(atom, Atom::ATOM)?.map(|data| {
-            data.iter()
-                .filter_map(|atom| map.get(&Atom::new(*atom)).copied())
-                .collect::<Vec<EwmhWindowType>>()
-        });
+        let type_ = self
+            .get_u32_list_property(atoms.window_type, Atom::ATOM)?
+            .map(|data| {
+                data.iter()
+                    .filter_map(|atom| map.get(&Atom::new(*atom)).copied())
+                    .collect::<Vec<EwmhWindowType>>()
+            });
 
         Ok(type_.unwrap_or(Vec::new()))
     }
@@ -318,51 +323,21 @@ impl Ewmh {
     pub fn set_number_of_desktops(&self, desktops: u32) -> Result<(), Error> {
         let atom = self.display.intern_atom("_NET_NUMBER_OF_DESKTOPS", false)?;
 
-        self.window.change_property(
-            atom,
-            Atom::CARDINAL,
-            PropFormat::Format32,
-            PropMode::Replace,
-            &desktops.to_le_bytes(),
-        )
+        self.window.set_property(atom, Atom::CARDINAL, &desktops)
     }
 
+    /// the getters do not check the type and format of the property as some window managers set
+    /// them with the wrong ones, the values are read from whatever data the property has
     fn get_u32_list_property(
         &self,
         property: Atom,
         type_: Atom,
     ) -> Result<Option<Vec<u32>>, Error> {
-        self.map_property(property, type_, |data, _| {
-            data.chunks(4)
-                .filter(|chunk| chunk.len() == 4)
-                .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                .collect::<Vec<u32>>()
-        })
-    }
-
-    fn set_u32_list_property(
-        &self,
-        property: Atom,
-        type_: Atom,
-        format: PropFormat,
-        values: &[u32],
-    ) -> Result<(), Error> {
-        let bytes = values
-            .iter()
-            .map(|x| x.to_le_bytes().to_vec())
-            .flatten()
-            .collect::<Vec<u8>>();
-
-        self.window
-            .change_property(property, type_, format, PropMode::Replace, &bytes)
+        self.map_property(property, type_, |data, _| lenient_u32_list(&data))
     }
 
     fn get_u32_property(&self, property: Atom, type_: Atom) -> Result<Option<u32>, Error> {
-        self.map_property(property, type_, |mut data, _| {
-            data.resize(4, 0);
-
-            u32::from_le_bytes([data[0], data[1], data[2], data[3]])
-        })
+        self.map_property(property, type_, |data, _| lenient_u32(&data))
     }
 
     fn map_property<F, R>(&self, property: Atom, type_: Atom, f: F) -> Result<Option<R>, Error>
@@ -377,3 +352,36 @@ impl Ewmh {
         Ok(wid)
     }
 }
+
+/// read 32 bit values from property data of any format, incomplete trailing bytes are ignored
+fn lenient_u32_list(data: &[u8]) -> Vec<u32> {
+    data.chunks_exact(4)
+        .map(|chunk| u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
+        .collect()
+}
+
+/// read a single 32 bit value from property data of any format, missing bytes are zero
+fn lenient_u32(data: &[u8]) -> u32 {
+    let mut bytes = [0; 4];
+
+    data.iter().take(4).zip(bytes.iter_mut()).for_each(|(byte, value)| *value = *byte);
+
+    u32::from_le_bytes(bytes)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_lenient_values() {
+        // a value set with format 8 or 16 instead of 32 is still read
+        assert_eq!(lenient_u32(&[2]), 2);
+        assert_eq!(lenient_u32(&[1, 1]), 257);
+        assert_eq!(lenient_u32(&[]), 0);
+        assert_eq!(lenient_u32(&[1, 0, 0, 0, 9]), 1);
+
+        assert_eq!(lenient_u32_list(&[1, 0, 0, 0, 2, 0, 0, 0, 3]), vec![1, 2]);
+        assert_eq!(lenient_u32_list(&[1, 0]), Vec::<u32>::new());
+    }
+}
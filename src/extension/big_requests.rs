@@ -0,0 +1,53 @@
+use crate::display::error::*;
+use crate::display::request::*;
+use crate::display::*;
+use crate::proto::*;
+
+#[non_exhaustive]
+pub struct MinorOpcode;
+
+impl MinorOpcode {
+    pub const ENABLE: u8 = 0;
+}
+
+pub struct BigRequests {
+    stream: Stream,
+    replies: Replies,
+    sequence: SequenceManager,
+    major_opcode: u8,
+}
+
+impl BigRequests {
+    pub(crate) fn new(
+        stream: Stream,
+        replies: Replies,
+        sequence: SequenceManager,
+        major_opcode: u8,
+    ) -> BigRequests {
+        BigRequests {
+            stream,
+            replies,
+            sequence,
+            major_opcode,
+        }
+    }
+
+    /// enable the extended length encoding and return the maximum request length in units of
+    /// 4 bytes
+    pub fn enable(&self) -> Result<u32, Error> {
+        let sequence = self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::BigRequestsEnable,
+            BigRequestsEnable {
+                opcode: self.major_opcode,
+                minor: MinorOpcode::ENABLE,
+                length: 1,
+            },
+        )?;
+
+        match self.replies.wait(sequence)? {
+            Reply::BigRequestsEnable(response) => Ok(response.maximum_request_length),
+            _ => Err(Error::UnexpectedReply),
+        }
+    }
+}
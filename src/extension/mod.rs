@@ -5,15 +5,25 @@
 #[cfg(feature = "xinerama")]
 pub mod xinerama;
 
+/// big requests allows requests that are larger than 256KiB, it is enabled when connecting
+pub mod big_requests;
+
+/// xc-misc lets a client find resource ids that are free again after it ran out of ids
+pub mod xc_misc;
+
 /// an enum for the supported x11 extensions
 pub enum Extension {
     Xinerama,
+    BigRequests,
+    XcMisc,
 }
 
 impl ToString for Extension {
     fn to_string(&self) -> String {
         match self {
             Extension::Xinerama => String::from("XINERAMA"),
+            Extension::BigRequests => String::from("BIG-REQUESTS"),
+            Extension::XcMisc => String::from("XC-MISC"),
         }
     }
 }
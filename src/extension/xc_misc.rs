@@ -0,0 +1,75 @@
+use crate::display::error::*;
+use crate::display::request::*;
+use crate::display::*;
+use crate::proto::*;
+
+#[non_exhaustive]
+pub struct MinorOpcode;
+
+impl MinorOpcode {
+    pub const GET_VERSION: u8 = 0;
+    pub const GET_XID_RANGE: u8 = 1;
+    pub const GET_XID_LIST: u8 = 2;
+}
+
+#[derive(Clone)]
+pub struct XcMisc {
+    stream: Stream,
+    replies: Replies,
+    sequence: SequenceManager,
+    major_opcode: u8,
+}
+
+impl XcMisc {
+    pub(crate) fn new(
+        stream: Stream,
+        replies: Replies,
+        sequence: SequenceManager,
+        major_opcode: u8,
+    ) -> XcMisc {
+        XcMisc {
+            stream,
+            replies,
+            sequence,
+            major_opcode,
+        }
+    }
+
+    /// get a contiguous range of resource ids that are not in use, returns the first id and the
+    /// amount of ids in the range
+    pub fn get_xid_range(&self) -> Result<(u32, u32), Error> {
+        let sequence = self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::XcMiscGetXidRange,
+            XcMiscGetXidRange {
+                opcode: self.major_opcode,
+                minor: MinorOpcode::GET_XID_RANGE,
+                length: 1,
+            },
+        )?;
+
+        match self.replies.wait(sequence)? {
+            Reply::XcMiscGetXidRange(response) => Ok((response.start_id, response.count)),
+            _ => Err(Error::UnexpectedReply),
+        }
+    }
+
+    /// get up to count resource ids that are not in use, the ids do not have to be contiguous
+    pub fn get_xid_list(&self, count: u32) -> Result<Vec<u32>, Error> {
+        let sequence = self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::XcMiscGetXidList,
+            XcMiscGetXidList {
+                opcode: self.major_opcode,
+                minor: MinorOpcode::GET_XID_LIST,
+                length: 2,
+                count,
+            },
+        )?;
+
+        match self.replies.wait(sequence)? {
+            Reply::XcMiscGetXidList { ids } => Ok(ids),
+            _ => Err(Error::UnexpectedReply),
+        }
+    }
+}
@@ -3,6 +3,8 @@ use crate::display::request::*;
 use crate::display::*;
 use crate::proto::*;
 
+use std::sync::Arc;
+
 #[non_exhaustive]
 pub struct MinorOpcode;
 
@@ -13,55 +15,65 @@ impl MinorOpcode {
 
 pub struct Xinerama {
     stream: Stream,
-    replies: Queue<Reply>,
+    replies: Replies,
     sequence: SequenceManager,
     major_opcode: u8,
+
+    /// keeps the connection open while the handle is alive
+    #[allow(dead_code)]
+    connection: Arc<Connection>,
 }
 
 impl Xinerama {
     pub(crate) fn new(
         stream: Stream,
-        replies: Queue<Reply>,
+        replies: Replies,
         sequence: SequenceManager,
         major_opcode: u8,
+        connection: Arc<Connection>,
     ) -> Xinerama {
         Xinerama {
             stream,
             replies,
             sequence,
             major_opcode,
+            connection,
         }
     }
 
     /// query the screens and return them in a vector
     pub fn query_screens(&self) -> Result<Vec<XineramaScreenInfo>, Error> {
-        self.sequence.append(ReplyKind::XineramaQueryScreens)?;
+        let sequence = self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::XineramaQueryScreens,
+            XineramaQueryScreens {
+                opcode: self.major_opcode,
+                minor: MinorOpcode::QUERY_SCREENS,
+                length: 1,
+            },
+        )?;
 
-        self.stream.send_encode(XineramaQueryScreens {
-            opcode: self.major_opcode,
-            minor: MinorOpcode::QUERY_SCREENS,
-            length: 1,
-        })?;
-
-        match self.replies.wait()? {
+        match self.replies.wait(sequence)? {
             Reply::XineramaQueryScreens { screens } => Ok(screens),
-            _ => unreachable!(),
+            _ => Err(Error::UnexpectedReply),
         }
     }
 
     /// returns true if xinerama is active
     pub fn is_active(&self) -> Result<bool, Error> {
-        self.sequence.append(ReplyKind::XineramaIsActive)?;
-
-        self.stream.send_encode(XineramaIsActive {
-            opcode: self.major_opcode,
-            minor: MinorOpcode::IS_ACTIVE,
-            length: 1,
-        })?;
+        let sequence = self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::XineramaIsActive,
+            XineramaIsActive {
+                opcode: self.major_opcode,
+                minor: MinorOpcode::IS_ACTIVE,
+                length: 1,
+            },
+        )?;
 
-        match self.replies.wait()? {
+        match self.replies.wait(sequence)? {
             Reply::XineramaIsActive(response) => Ok(response.state != 0),
-            _ => unreachable!(),
+            _ => Err(Error::UnexpectedReply),
         }
     }
 }
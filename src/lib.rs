@@ -79,6 +79,7 @@
 //! - `clipboard` - extensible builtin clipboard functionality
 //! - `extras` - enables some convencience functions that arent a part of the official protocol
 //! - `ewmh` - enable convencience functions for working with [EWMH](https://specifications.freedesktop.org/wm-spec/1.3/)
+//! - `async` - runtime agnostic futures for replies and a stream of events, they are woken by the listener thread
 
 #![cfg_attr(docsrs, feature(doc_cfg, doc_auto_cfg))]
 
@@ -1,12 +1,19 @@
 use crate::display::error::Error;
 use crate::display::request::{self, *};
-use crate::display::Atom;
+use crate::display::{Atom, Stream};
 use crate::keyboard::Keysym;
 use crate::window::ConfigureValue;
 
-use std::collections::VecDeque;
-use std::sync::atomic::{AtomicU16, Ordering};
-use std::sync::{Arc, Condvar, Mutex, MutexGuard};
+use std::collections::{HashMap, HashSet, VecDeque};
+use std::io::{Read, Write};
+use std::os::fd::{AsRawFd, RawFd};
+use std::os::unix::net::UnixStream;
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Condvar, Mutex, MutexGuard, OnceLock};
+use std::time::{Duration, Instant};
+
+#[cfg(feature = "async")]
+use std::task::{Context, Poll, Waker};
 
 macro_rules! lock {
     ($mutex:expr) => {
@@ -29,6 +36,7 @@ impl Response {
     pub const ENTER_NOTIFY: u8 = 7;
     pub const FOCUS_IN: u8 = 9;
     pub const FOCUS_OUT: u8 = 10;
+    pub const KEYMAP_NOTIFY: u8 = 11;
     pub const CREATE_NOTIFY: u8 = 16;
     pub const DESTROY_NOTIFY: u8 = 17;
     pub const UNMAP_NOTIFY: u8 = 18;
@@ -46,6 +54,7 @@ impl Response {
     pub const SELECTION_NOTIFY: u8 = 31;
     pub const CLIENT_MESSAGE: u8 = 33;
     pub const MAPPING_NOTIFY: u8 = 34;
+    pub const GENERIC_EVENT: u8 = 35;
 }
 
 #[non_exhaustive]
@@ -188,6 +197,133 @@ impl Opcode {
     pub const SET_MODIFIER_MAPPING: u8 = 118;
     pub const GET_MODIFIER_MAPPING: u8 = 119;
     pub const NO_OPERATION: u8 = 127;
+
+    /// the name of a core request as it is written in the protocol specification, extension
+    /// requests have a major opcode of 128 or higher and no name
+    pub fn name(opcode: u8) -> Option<&'static str> {
+        Some(match opcode {
+            Opcode::CREATE_WINDOW => "CreateWindow",
+            Opcode::CHANGE_WINDOW_ATTRIBUTES => "ChangeWindowAttributes",
+            Opcode::GET_WINDOW_ATTRIBUTES => "GetWindowAttributes",
+            Opcode::DESTROY_WINDOW => "DestroyWindow",
+            Opcode::DESTROY_SUBWINDOWS => "DestroySubwindows",
+            Opcode::CHANGE_SAVE_SET => "ChangeSaveSet",
+            Opcode::REPARENT_WINDOW => "ReparentWindow",
+            Opcode::MAP_WINDOW => "MapWindow",
+            Opcode::MAP_SUBWINDOWS => "MapSubwindows",
+            Opcode::UNMAP_WINDOW => "UnmapWindow",
+            Opcode::UNMAP_SUBWINDOWS => "UnmapSubwindows",
+            Opcode::CONFIGURE_WINDOW => "ConfigureWindow",
+            Opcode::CIRCULATE_WINDOW => "CirculateWindow",
+            Opcode::GET_GEOMETRY => "GetGeometry",
+            Opcode::QUERY_TREE => "QueryTree",
+            Opcode::INTERN_ATOM => "InternAtom",
+            Opcode::GET_ATOM_NAME => "GetAtomName",
+            Opcode::CHANGE_PROPERTY => "ChangeProperty",
+            Opcode::DELETE_PROPERTY => "DeleteProperty",
+            Opcode::GET_PROPERTY => "GetProperty",
+            Opcode::SET_SELECTION_OWNER => "SetSelectionOwner",
+            Opcode::GET_SELECTION_OWNER => "GetSelectionOwner",
+            Opcode::CONVERT_SELECTION => "ConvertSelection",
+            Opcode::SEND_EVENT => "SendEvent",
+            Opcode::GRAB_POINTER => "GrabPointer",
+            Opcode::UNGRAB_POINTER => "UngrabPointer",
+            Opcode::GRAB_BUTTON => "GrabButton",
+            Opcode::UNGRAB_BUTTON => "UngrabButton",
+            Opcode::CHANGE_ACTIVE_POINTER_GRAB => "ChangeActivePointerGrab",
+            Opcode::GRAB_KEYBOARD => "GrabKeyboard",
+            Opcode::UNGRAB_KEYBOARD => "UngrabKeyboard",
+            Opcode::GRAB_KEY => "GrabKey",
+            Opcode::UNGRAB_KEY => "UngrabKey",
+            Opcode::ALLOW_EVENTS => "AllowEvents",
+            Opcode::GRAB_SERVER => "GrabServer",
+            Opcode::UNGRAB_SERVER => "UngrabServer",
+            Opcode::QUERY_POINTER => "QueryPointer",
+            Opcode::GET_MOTION_EVENTS => "GetMotionEvents",
+            Opcode::TRANSLATE_COORDINATES => "TranslateCoordinates",
+            Opcode::WARP_POINTER => "WarpPointer",
+            Opcode::SET_INPUT_FOCUS => "SetInputFocus",
+            Opcode::GET_INPUT_FOCUS => "GetInputFocus",
+            Opcode::QUERY_KEYMAP => "QueryKeymap",
+            Opcode::OPEN_FONT => "OpenFont",
+            Opcode::CLOSE_FONT => "CloseFont",
+            Opcode::QUERY_FONT => "QueryFont",
+            Opcode::QUERY_TEXT_EXTENTS => "QueryTextExtents",
+            Opcode::LIST_FONTS => "ListFonts",
+            Opcode::LIST_FONTS_WITH_INFO => "ListFontsWithInfo",
+            Opcode::SET_FONT_PATH => "SetFontPath",
+            Opcode::GET_FONT_PATH => "GetFontPath",
+            Opcode::CREATE_PIXMAP => "CreatePixmap",
+            Opcode::FREE_PIXMAP => "FreePixmap",
+            Opcode::CREATE_GC => "CreateGC",
+            Opcode::CHANGE_GC => "ChangeGC",
+            Opcode::COPY_GC => "CopyGC",
+            Opcode::SET_DASHES => "SetDashes",
+            Opcode::SET_CLIP_RECTANGLES => "SetClipRectangles",
+            Opcode::FREE_GC => "FreeGC",
+            Opcode::CLEAR_AREA => "ClearArea",
+            Opcode::COPY_AREA => "CopyArea",
+            Opcode::COPY_PLANE => "CopyPlane",
+            Opcode::POLY_POINT => "PolyPoint",
+            Opcode::POLY_LINE => "PolyLine",
+            Opcode::POLY_SEGMENT => "PolySegment",
+            Opcode::POLY_RECTANGLE => "PolyRectangle",
+            Opcode::POLY_ARC => "PolyArc",
+            Opcode::FILL_POLY => "FillPoly",
+            Opcode::POLY_FILL_RECTANGLE => "PolyFillRectangle",
+            Opcode::POLY_FILL_ARC => "PolyFillArc",
+            Opcode::PUT_IMAGE => "PutImage",
+            Opcode::GET_IMAGE => "GetImage",
+            Opcode::POLY_TEXT8 => "PolyText8",
+            Opcode::POLY_TEXT16 => "PolyText16",
+            Opcode::IMAGE_TEXT8 => "ImageText8",
+            Opcode::IMAGE_TEXT16 => "ImageText16",
+            Opcode::CREATE_COLORMAP => "CreateColormap",
+            Opcode::FREE_COLORMAP => "FreeColormap",
+            Opcode::COPY_COLORMAP_AND_FREE => "CopyColormapAndFree",
+            Opcode::INSTALL_COLORMAP => "InstallColormap",
+            Opcode::UNINSTALL_COLORMAP => "UninstallColormap",
+            Opcode::LIST_INSTALLED_COLORMAPS => "ListInstalledColormaps",
+            Opcode::ALLOC_COLOR => "AllocColor",
+            Opcode::ALLOC_NAMED_COLOR => "AllocNamedColor",
+            Opcode::ALLOC_COLOR_CELLS => "AllocColorCells",
+            Opcode::ALLOC_COLOR_PLANES => "AllocColorPlanes",
+            Opcode::FREE_COLORS => "FreeColors",
+            Opcode::STORE_COLORS => "StoreColors",
+            Opcode::STORE_NAMED_COLOR => "StoreNamedColor",
+            Opcode::QUERY_COLORS => "QueryColors",
+            Opcode::LOOKUP_COLOR => "LookupColor",
+            Opcode::CREATE_CURSOR => "CreateCursor",
+            Opcode::CREATE_GLYPH_CURSOR => "CreateGlyphCursor",
+            Opcode::FREE_CURSOR => "FreeCursor",
+            Opcode::RECOLOR_CURSOR => "RecolorCursor",
+            Opcode::QUERY_BEST_SIZE => "QueryBestSize",
+            Opcode::QUERY_EXTENSION => "QueryExtension",
+            Opcode::LIST_EXTENSIONS => "ListExtensions",
+            Opcode::CHANGE_KEYBOARD_MAPPING => "ChangeKeyboardMapping",
+            Opcode::GET_KEYBOARD_MAPPING => "GetKeyboardMapping",
+            Opcode::CHANGE_KEYBOARD_CONTROL => "ChangeKeyboardControl",
+            Opcode::GET_KEYBOARD_CONTROL => "GetKeyboardControl",
+            Opcode::BELL => "Bell",
+            Opcode::CHANGE_POINTER_CONTROL => "ChangePointerControl",
+            Opcode::GET_POINTER_CONTROL => "GetPointerControl",
+            Opcode::SET_SCREENSAVER => "SetScreenSaver",
+            Opcode::GET_SCREENSAVER => "GetScreenSaver",
+            Opcode::CHANGE_HOSTS => "ChangeHosts",
+            Opcode::LIST_HOSTS => "ListHosts",
+            Opcode::SET_ACCESS_CONTROL => "SetAccessControl",
+            Opcode::SET_CLOSE_DOWN_MODE => "SetCloseDownMode",
+            Opcode::KILL_CLIENT => "KillClient",
+            Opcode::ROTATE_PROPERTIES => "RotateProperties",
+            Opcode::FORCE_SCREENSAVER => "ForceScreenSaver",
+            Opcode::SET_POINTER_MAPPING => "SetPointerMapping",
+            Opcode::GET_POINTER_MAPPING => "GetPointerMapping",
+            Opcode::SET_MODIFIER_MAPPING => "SetModifierMapping",
+            Opcode::GET_MODIFIER_MAPPING => "GetModifierMapping",
+            Opcode::NO_OPERATION => "NoOperation",
+            _ => return None,
+        })
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd)]
@@ -237,13 +373,204 @@ impl From<u8> for ErrorCode {
     }
 }
 
+/// the outcome of reading a response on the thread that waits for it
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub(crate) enum Drive {
+    Read,
+    /// another thread was reading, what the caller waits for may have arrived in the meantime
+    Retry,
+    TimedOut,
+}
+
+/// reads responses on the threads that wait for them, this replaces the listener thread for
+/// connections in single threaded mode
+pub(crate) trait Driver: Send + Sync {
+    /// read and handle a single response, a deadline that has passed only reads what is already
+    /// available without blocking
+    fn drive(&self, deadline: Option<Instant>) -> Result<Drive, Error>;
+}
+
+/// signal wakes the threads waiting on a connection, it is shared by the event queue and the
+/// replies so that a thread waiting for events also wakes up for replies and errors
+#[derive(Clone, Default)]
+pub struct Signal {
+    generation: Arc<Mutex<u64>>,
+    cond: Arc<Condvar>,
+    driver: Arc<Mutex<Option<Arc<dyn Driver>>>>,
+
+    /// the task waiting on the event queue, tasks waiting on replies are kept by the replies
+    #[cfg(feature = "async")]
+    events: Arc<Mutex<Option<Waker>>>,
+}
+
+impl std::fmt::Debug for Signal {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("Signal")
+            .field("generation", &self.generation)
+            .finish_non_exhaustive()
+    }
+}
+
+impl Signal {
+    pub fn new() -> Signal {
+        Signal::default()
+    }
+
+    /// lock the signal, everything that is checked while the lock is held can not change without
+    /// waking up a following wait
+    pub fn lock(&self) -> Result<MutexGuard<'_, u64>, Error> {
+        lock!(self.generation)
+    }
+
+    pub(crate) fn set_driver(&self, driver: Option<Arc<dyn Driver>>) -> Result<(), Error> {
+        *lock!(self.driver)? = driver;
+
+        Ok(())
+    }
+
+    /// read a response that is already available when there is no listener thread, returns
+    /// true if a response was read
+    pub(crate) fn drive_now(&self) -> Result<bool, Error> {
+        let driver = lock!(self.driver)?.clone();
+
+        match driver {
+            Some(driver) => Ok(driver.drive(Some(Instant::now()))? == Drive::Read),
+            None => Ok(false),
+        }
+    }
+
+    /// wait until the signal is notified, returns none once the deadline has passed
+    ///
+    /// without a listener thread the waiting thread reads the next response itself, the lock is
+    /// released while reading so the response can be handed to the thread waiting on it
+    pub fn wait<'a>(
+        &'a self,
+        guard: MutexGuard<'a, u64>,
+        deadline: Option<Instant>,
+    ) -> Result<Option<MutexGuard<'a, u64>>, Error> {
+        let driver = lock!(self.driver)?.clone();
+
+        if let Some(driver) = driver {
+            drop(guard);
+
+            return match driver.drive(deadline)? {
+                Drive::Read | Drive::Retry => self.lock().map(Some),
+                Drive::TimedOut => Ok(None),
+            };
+        }
+
+        match deadline {
+            Some(deadline) => {
+                let timeout = deadline.saturating_duration_since(Instant::now());
+
+                if timeout.is_zero() {
+                    return Ok(None);
+                }
+
+                self.cond
+                    .wait_timeout(guard, timeout)
+                    .map(|(guard, _)| Some(guard))
+                    .map_err(|_| Error::FailedToWait)
+            }
+            None => self
+                .cond
+                .wait(guard)
+                .map(Some)
+                .map_err(|_| Error::FailedToWait),
+        }
+    }
+
+    /// wake up every waiting thread
+    pub fn notify(&self) -> Result<(), Error> {
+        let mut generation = lock!(self.generation)?;
+
+        *generation = generation.wrapping_add(1);
+
+        self.cond.notify_all();
+
+        Ok(())
+    }
+
+    /// wake up every waiting thread and the task waiting on the event queue
+    pub fn notify_events(&self) -> Result<(), Error> {
+        self.notify()?;
+
+        #[cfg(feature = "async")]
+        {
+            // a waker may poll its future right away which registers it again
+            let waker = lock!(self.events)?.take();
+
+            if let Some(waker) = waker {
+                waker.wake();
+            }
+        }
+
+        Ok(())
+    }
+
+    /// wake up the task on the next event or error, only a single task is woken so the events
+    /// should be taken by one task, the queue must be checked after this so that nothing that
+    /// arrives in between is missed
+    #[cfg(feature = "async")]
+    pub(crate) fn register_events(&self, waker: &Waker) -> Result<(), Error> {
+        let mut events = lock!(self.events)?;
+
+        if !events.as_ref().is_some_and(|other| other.will_wake(waker)) {
+            *events = Some(waker.clone());
+        }
+
+        Ok(())
+    }
+}
+
+/// a socket pair that is readable while a queue has elements, this lets the queue be watched
+/// with poll or epoll next to other file descriptors
+#[derive(Debug)]
+struct Readiness {
+    reader: UnixStream,
+    writer: UnixStream,
+}
+
+impl Readiness {
+    fn new() -> Result<Readiness, Error> {
+        let (reader, writer) = UnixStream::pair()?;
+
+        reader.set_nonblocking(true)?;
+        writer.set_nonblocking(true)?;
+
+        Ok(Readiness { reader, writer })
+    }
+
+    /// make the reader readable, a single byte is enough as the byte is only written when the
+    /// queue stops being empty
+    fn set(&self) {
+        let _ = (&self.writer).write(&[0]);
+    }
+
+    /// drain the reader so it is no longer readable
+    fn clear(&self) {
+        let mut buffer = [0u8; 16];
+
+        while let Ok(read) = (&self.reader).read(&mut buffer) {
+            if read == 0 {
+                break;
+            }
+        }
+    }
+}
+
+/// the errors of requests without a reply, each with the sequence number of the request
+pub type Errors = Arc<Mutex<Vec<(u64, Error)>>>;
+
 /// queue is a single-producer single-consumer queue implementation
 
 #[derive(Debug)]
 pub struct Queue<T: std::fmt::Debug + Clone> {
-    cond: Arc<Condvar>,
+    signal: Signal,
     queue: Arc<Mutex<VecDeque<T>>>,
-    errors: Arc<Mutex<Vec<Error>>>,
+    errors: Errors,
+    closed: Arc<AtomicBool>,
+    readiness: Arc<OnceLock<Readiness>>,
 }
 
 impl<T> Clone for Queue<T>
@@ -252,9 +579,11 @@ where
 {
     fn clone(&self) -> Queue<T> {
         Queue {
-            cond: self.cond.clone(),
+            signal: self.signal.clone(),
             queue: self.queue.clone(),
             errors: self.errors.clone(),
+            closed: self.closed.clone(),
+            readiness: self.readiness.clone(),
         }
     }
 }
@@ -263,61 +592,407 @@ impl<T> Queue<T>
 where
     T: std::fmt::Debug + Clone,
 {
-    pub fn new(errors: Arc<Mutex<Vec<Error>>>) -> Queue<T> {
+    pub fn new(errors: Errors, closed: Arc<AtomicBool>, signal: Signal) -> Queue<T> {
         Queue {
-            cond: Arc::new(Condvar::new()),
+            signal,
             queue: Arc::new(Mutex::new(VecDeque::new())),
             errors,
+            closed,
+            readiness: Arc::new(OnceLock::new()),
         }
     }
 
     #[inline]
     pub fn poll(&self) -> Result<bool, Error> {
+        self.fill()?;
         self.poll_error()?;
 
-        Ok(!lock!(self.queue)?.is_empty())
+        let empty = lock!(self.queue)?.is_empty();
+
+        if empty && self.closed.load(Ordering::SeqCst) {
+            return Err(Error::ConnectionClosed);
+        }
+
+        Ok(!empty)
     }
 
     #[inline]
     pub fn pop(&self, guard: &mut MutexGuard<'_, VecDeque<T>>) -> Result<Option<T>, Error> {
         self.poll_error()?;
 
-        Ok(guard.pop_front())
+        let element = guard.pop_front();
+
+        // a closed queue stays readable so that a poll loop sees the connection close
+        if guard.is_empty() && !self.closed.load(Ordering::SeqCst) {
+            if let Some(readiness) = self.readiness.get() {
+                readiness.clear();
+            }
+        }
+
+        Ok(element)
+    }
+
+    /// get a file descriptor that is readable while the queue has elements or once the connection
+    /// is closed, the descriptor must only be polled and never read from
+    pub fn raw_fd(&self) -> Result<RawFd, Error> {
+        let queue = lock!(self.queue)?;
+
+        if let Some(readiness) = self.readiness.get() {
+            return Ok(readiness.reader.as_raw_fd());
+        }
+
+        let readiness = Readiness::new()?;
+
+        if !queue.is_empty() || self.closed.load(Ordering::SeqCst) {
+            readiness.set();
+        }
+
+        Ok(self.readiness.get_or_init(|| readiness).reader.as_raw_fd())
+    }
+
+    /// take the next element without waiting
+    pub fn try_pop(&self) -> Result<Option<T>, Error> {
+        self.fill()?;
+        self.take()
+    }
+
+    /// read the responses that already arrived if there is no listener thread to do it
+    fn fill(&self) -> Result<(), Error> {
+        while lock!(self.queue)?.is_empty() && self.signal.drive_now()? {}
+
+        Ok(())
+    }
+
+    fn take(&self) -> Result<Option<T>, Error> {
+        let element = self.pop(&mut lock!(self.queue)?)?;
+
+        if element.is_none() && self.closed.load(Ordering::SeqCst) {
+            return Err(Error::ConnectionClosed);
+        }
+
+        Ok(element)
+    }
+
+    /// take the next element or wake up the task once one arrives
+    #[cfg(feature = "async")]
+    pub fn poll_pop(&self, cx: &mut Context<'_>) -> Poll<Result<T, Error>> {
+        self.signal.register_events(cx.waker())?;
+
+        match self.try_pop()? {
+            Some(element) => Poll::Ready(Ok(element)),
+            None => Poll::Pending,
+        }
     }
 
     pub fn wait(&self) -> Result<T, Error> {
-        let mut lock = lock!(self.queue)?;
+        self.wait_until(None)?.ok_or(Error::FailedToWait)
+    }
+
+    /// wait for the next element, returns none if nothing arrived before the timeout
+    pub fn wait_timeout(&self, timeout: Duration) -> Result<Option<T>, Error> {
+        self.wait_until(Instant::now().checked_add(timeout))
+    }
+
+    fn wait_until(&self, deadline: Option<Instant>) -> Result<Option<T>, Error> {
+        let mut guard = self.signal.lock()?;
+
+        loop {
+            if let Some(element) = self.take()? {
+                return Ok(Some(element));
+            }
+
+            match self.signal.wait(guard, deadline)? {
+                Some(next) => guard = next,
+                None => return Ok(None),
+            }
+        }
+    }
+
+    /// wait until an element or error is ready or the signal is notified for any other reason,
+    /// returns false if the deadline passed first, nothing is taken from the queue
+    pub fn wait_ready(&self, deadline: Option<Instant>) -> Result<bool, Error> {
+        // reading a response notifies the signal so this has to happen before it is locked
+        self.fill()?;
+
+        let mut guard = self.signal.lock()?;
+        let generation = *guard;
 
         loop {
-            if let Some(element) = self.pop(&mut lock)? {
-                return Ok(element);
-            } else {
-                lock = self.cond.wait(lock).map_err(|_| Error::FailedToWait)?;
+            if self.is_ready()? {
+                return Ok(true);
+            }
+
+            match self.signal.wait(guard, deadline)? {
+                Some(next) if *next != generation => return Ok(true),
+                Some(next) => guard = next,
+                None => return Ok(false),
             }
         }
     }
 
+    /// check for an element or error without taking it or reading responses, the signal may be
+    /// locked as nothing is notified
+    fn is_ready(&self) -> Result<bool, Error> {
+        if !lock!(self.queue)?.is_empty() || !lock!(self.errors)?.is_empty() {
+            return Ok(true);
+        }
+
+        if self.closed.load(Ordering::SeqCst) {
+            return Err(Error::ConnectionClosed);
+        }
+
+        Ok(false)
+    }
+
+    /// wake up every waiter after the connection was closed, events that are already queued can
+    /// still be read
+    pub fn close(&self) -> Result<(), Error> {
+        {
+            let queue = lock!(self.queue)?;
+
+            if let Some(readiness) = self.readiness.get().filter(|_| queue.is_empty()) {
+                readiness.set();
+            }
+        }
+
+        self.signal.notify_events()
+    }
+
     #[inline]
     pub fn push(&self, element: T) -> Result<(), Error> {
-        lock!(self.queue)?.push_back(element);
+        {
+            let mut queue = lock!(self.queue)?;
 
-        self.cond.notify_all();
+            if let Some(readiness) = self.readiness.get().filter(|_| queue.is_empty()) {
+                readiness.set();
+            }
+
+            queue.push_back(element);
+        }
+
+        self.signal.notify_events()
+    }
+
+    #[inline]
+    pub fn push_error(&self, sequence: u64, error: Error) -> Result<(), Error> {
+        lock!(self.errors)?.push((sequence, error));
+
+        self.signal.notify_events()
+    }
+
+    /// take the oldest error, errors are returned in the order their requests were sent
+    #[inline]
+    pub fn poll_error(&self) -> Result<(), Error> {
+        let mut errors = lock!(self.errors)?;
+
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        Err(errors.remove(0).1)
+    }
+}
+
+/// replies routes every reply to the thread waiting on the sequence number of its request, this
+/// allows any number of display clones to wait on replies at the same time
+pub struct Replies {
+    stream: Stream,
+    signal: Signal,
+    replies: Arc<Mutex<ReplySlots>>,
+    errors: Errors,
+}
+
+/// abandoned holds the sequence numbers that nobody waits on anymore, their replies are dropped
+/// as they arrive instead of being kept for the lifetime of the connection
+#[derive(Debug, Default)]
+struct ReplySlots {
+    replies: HashMap<u64, Result<Reply, Error>>,
+    abandoned: HashSet<u64>,
+
+    /// the tasks waiting on replies, a task is only woken up by the reply to its own request
+    #[cfg(feature = "async")]
+    wakers: HashMap<u64, Waker>,
+}
+
+impl ReplySlots {
+    fn insert(&mut self, sequence: u64, reply: Result<Reply, Error>) {
+        if !self.abandoned.remove(&sequence) {
+            self.replies.insert(sequence, reply);
+        }
+    }
+}
+
+impl Clone for Replies {
+    fn clone(&self) -> Replies {
+        Replies {
+            stream: self.stream.clone(),
+            signal: self.signal.clone(),
+            replies: self.replies.clone(),
+            errors: self.errors.clone(),
+        }
+    }
+}
+
+impl Replies {
+    pub fn new(stream: Stream, errors: Errors, signal: Signal) -> Replies {
+        Replies {
+            stream,
+            signal,
+            replies: Arc::new(Mutex::new(ReplySlots::default())),
+            errors,
+        }
+    }
+
+    /// wait for the reply to the request with the sequence number, the output buffer is flushed
+    /// first as the request may still be waiting in it
+    pub fn wait(&self, sequence: u64) -> Result<Reply, Error> {
+        self.stream.flush()?;
+
+        let mut guard = self.signal.lock()?;
+
+        loop {
+            let reply = self.take(&mut *lock!(self.replies)?, sequence);
+
+            if let Some(reply) = reply {
+                return reply;
+            }
+
+            guard = self.signal.wait(guard, None)?.ok_or(Error::FailedToWait)?;
+        }
+    }
+
+    /// take the reply to the request with the sequence number if it arrived, errors of requests
+    /// without a reply are left for whoever polls them as they belong to a different request
+    fn take(&self, slots: &mut ReplySlots, sequence: u64) -> Option<Result<Reply, Error>> {
+        if let Some(reply) = slots.replies.remove(&sequence) {
+            return Some(reply);
+        }
+
+        self.stream
+            .is_closed()
+            .then_some(Err(Error::ConnectionClosed))
+    }
+
+    /// take the reply to the request with the sequence number or wake up the task once it
+    /// arrives, the output buffer is flushed first as the request may still be waiting in it
+    #[cfg(feature = "async")]
+    pub fn poll_wait(&self, sequence: u64, cx: &mut Context<'_>) -> Poll<Result<Reply, Error>> {
+        self.stream.flush()?;
+
+        // the replies stay locked until the waker is stored so the reply can not arrive in between
+        let mut slots = lock!(self.replies)?;
+
+        match self.take(&mut slots, sequence) {
+            Some(reply) => {
+                slots.wakers.remove(&sequence);
+
+                Poll::Ready(reply)
+            }
+            None => {
+                if !slots
+                    .wakers
+                    .get(&sequence)
+                    .is_some_and(|waker| waker.will_wake(cx.waker()))
+                {
+                    slots.wakers.insert(sequence, cx.waker().clone());
+                }
+
+                Poll::Pending
+            }
+        }
+    }
+
+    /// stop waiting on the request with the sequence number, its reply is dropped when it arrives
+    #[cfg(feature = "async")]
+    pub(crate) fn abandon(&self, sequence: u64) -> Result<(), Error> {
+        let mut lock = lock!(self.replies)?;
+
+        lock.wakers.remove(&sequence);
+
+        if lock.replies.remove(&sequence).is_none() {
+            lock.abandoned.insert(sequence);
+        }
+
+        Ok(())
+    }
+
+    /// wake up every waiter after the connection was closed
+    pub fn close(&self) -> Result<(), Error> {
+        #[cfg(feature = "async")]
+        let wakers = std::mem::take(&mut lock!(self.replies)?.wakers);
+
+        self.signal.notify()?;
+
+        #[cfg(feature = "async")]
+        wakers.into_values().for_each(Waker::wake);
 
         Ok(())
     }
 
     #[inline]
-    pub fn push_error(&self, error: Error) -> Result<(), Error> {
-        lock!(self.errors)?.push(error);
+    pub fn push(&self, sequence: u64, reply: Reply) -> Result<(), Error> {
+        self.deliver(sequence, Ok(reply))
+    }
 
-        self.cond.notify_all();
+    /// deliver an error to the thread waiting on the sequence number instead of a reply
+    #[inline]
+    pub fn push_sequence_error(&self, sequence: u64, error: Error) -> Result<(), Error> {
+        self.deliver(sequence, Err(error))
+    }
+
+    /// store the reply and wake up the thread or task waiting on its sequence number
+    fn deliver(&self, sequence: u64, reply: Result<Reply, Error>) -> Result<(), Error> {
+        let mut lock = lock!(self.replies)?;
+
+        lock.insert(sequence, reply);
+
+        // a waker may poll its future right away which locks the replies again
+        #[cfg(feature = "async")]
+        let waker = lock.wakers.remove(&sequence);
+
+        drop(lock);
+
+        self.signal.notify()?;
+
+        #[cfg(feature = "async")]
+        if let Some(waker) = waker {
+            waker.wake();
+        }
 
         Ok(())
     }
 
+    /// report an error of a request without a reply, the sequence number is the one of the
+    /// request that caused it
+    #[inline]
+    pub fn push_error(&self, sequence: u64, error: Error) -> Result<(), Error> {
+        lock!(self.errors)?.push((sequence, error));
+
+        // the errors are returned by the event queue so the task waiting on it is woken up
+        self.signal.notify_events()
+    }
+
+    /// take the oldest error, errors are returned in the order their requests were sent
     #[inline]
     pub fn poll_error(&self) -> Result<(), Error> {
-        lock!(self.errors)?.pop().map_or(Ok(()), |error| Err(error))
+        let mut errors = lock!(self.errors)?;
+
+        if errors.is_empty() {
+            return Ok(());
+        }
+
+        Err(errors.remove(0).1)
+    }
+
+    /// take the oldest error caused by a request up to the sequence number, errors of later
+    /// requests are left for their own sync
+    pub fn poll_error_until(&self, sequence: u64) -> Result<(), Error> {
+        let mut errors = lock!(self.errors)?;
+
+        match errors.iter().position(|(other, _)| *other <= sequence) {
+            Some(index) => Err(errors.remove(index).1),
+            None => Ok(()),
+        }
     }
 }
 
@@ -333,6 +1008,8 @@ pub enum Reply {
     GetSelectionOwner(GetSelectionOwnerResponse),
     QueryTree(TreeNode),
     GrabKeyboard(GrabKeyboardStatus),
+    BigRequestsEnable(BigRequestsEnableResponse),
+    XcMiscGetXidRange(XcMiscGetXidRangeResponse),
 
     #[cfg(feature = "xinerama")]
     XineramaIsActive(XineramaIsActiveResponse),
@@ -347,16 +1024,24 @@ pub enum Reply {
     },
     GetProperty {
         type_: Atom,
+        format: u8,
+        bytes_after: u32,
         value: Vec<u8>,
     },
     GetKeyboardMapping {
         keysyms: Vec<Keysym>,
         keysyms_per_keycode: u8,
     },
+    XcMiscGetXidList {
+        ids: Vec<u32>,
+    },
 }
 
 #[derive(Debug)]
 pub enum ReplyKind {
+    /// a reply that is read and dropped, used to keep the sequence numbers from wrapping around
+    /// when many requests without a reply are sent in a row
+    Discard,
     InternAtom,
     GetProperty,
     GetWindowAttributes,
@@ -370,6 +1055,9 @@ pub enum ReplyKind {
     GetAtomName,
     QueryTree,
     GrabKeyboard,
+    BigRequestsEnable,
+    XcMiscGetXidRange,
+    XcMiscGetXidList,
 
     #[cfg(feature = "xinerama")]
     XineramaIsActive,
@@ -380,49 +1068,102 @@ pub enum ReplyKind {
 
 #[derive(Debug)]
 pub struct Sequence {
-    pub id: u16,
+    pub id: u64,
     pub kind: ReplyKind,
 }
 
 impl Sequence {
-    pub fn new(id: u16, kind: ReplyKind) -> Sequence {
+    pub fn new(id: u64, kind: ReplyKind) -> Sequence {
         Sequence { id, kind }
     }
 }
 
+/// the server only sends the lower 16 bits of a sequence number, the sequence manager keeps
+/// track of the full 64 bit sequence numbers so that long lived connections never mix up replies
 #[derive(Clone)]
 pub struct SequenceManager {
-    id: Arc<AtomicU16>,
-    sequences: Arc<Mutex<Vec<Sequence>>>,
+    id: Arc<AtomicU64>,
+    last_reply: Arc<AtomicU64>,
+    last_read: Arc<AtomicU64>,
+    sequences: Arc<Mutex<VecDeque<Sequence>>>,
 }
 
 impl SequenceManager {
     pub fn new() -> SequenceManager {
         SequenceManager {
-            id: Arc::new(AtomicU16::default()),
-            sequences: Arc::new(Mutex::new(Vec::new())),
+            id: Arc::new(AtomicU64::default()),
+            last_reply: Arc::new(AtomicU64::default()),
+            last_read: Arc::new(AtomicU64::default()),
+            sequences: Arc::new(Mutex::new(VecDeque::new())),
         }
     }
 
-    pub fn get(&self, id: u16) -> Result<Sequence, Error> {
+    /// widen a 16 bit sequence number read from the server to the full sequence number, this
+    /// must be called for every response in the order they are read
+    pub fn widen(&self, id: u16) -> u64 {
+        let last = self.last_read.load(Ordering::Relaxed);
+
+        let mut sequence = (last & !0xffff) | id as u64;
+
+        if sequence < last {
+            sequence += 0x10000;
+        }
+
+        self.last_read.store(sequence, Ordering::Relaxed);
+
+        sequence
+    }
+
+    /// returns the last sequence number read from the server
+    pub fn last_read(&self) -> u64 {
+        self.last_read.load(Ordering::Relaxed)
+    }
+
+    /// remove the pending request with the sequence number, responses arrive in the order of
+    /// the requests so any request before it will never get a reply
+    pub fn get(&self, id: u64) -> Result<Sequence, Error> {
         let mut lock = lock!(self.sequences)?;
 
-        match lock.iter().position(|sequence| sequence.id == id) {
-            Some(index) => Ok(lock.remove(index)),
-            None => Err(Error::InvalidId),
+        match lock.front() {
+            Some(sequence) if sequence.id == id => lock.pop_front().ok_or(Error::InvalidId),
+            _ => Err(Error::InvalidId),
         }
     }
 
-    pub fn skip(&self) {
-        self.id.fetch_add(1, Ordering::Relaxed);
+    /// remove every pending request with a sequence number lower than id, these requests will
+    /// never receive a response
+    pub fn prune(&self, id: u64) -> Result<Vec<Sequence>, Error> {
+        let mut lock = lock!(self.sequences)?;
+
+        let stale = lock.iter().take_while(|sequence| sequence.id < id).count();
+
+        Ok(lock.drain(..stale).collect())
+    }
+
+    /// returns true when the next request without a reply would make the sequence numbers
+    /// ambiguous, a request with a reply must be sent first
+    pub fn needs_sync(&self) -> bool {
+        let id = self.id.load(Ordering::Relaxed);
+
+        id - self.last_reply.load(Ordering::Relaxed) >= u16::MAX as u64
+    }
+
+    /// skip the sequence number of a request without a reply, this must only be called while the
+    /// writer of the stream is locked so that the sequence numbers match the order of the requests
+    pub fn skip(&self) -> u64 {
+        self.id.fetch_add(1, Ordering::Relaxed) + 1
     }
 
-    pub fn append(&self, kind: ReplyKind) -> Result<(), Error> {
-        self.skip();
+    /// register a request that generates a reply, this must only be called while the writer of
+    /// the stream is locked so that the sequence numbers match the order of the requests
+    pub fn append(&self, kind: ReplyKind) -> Result<u64, Error> {
+        let id = self.skip();
 
-        lock!(self.sequences)?.push(Sequence::new(self.id.load(Ordering::Relaxed), kind));
+        self.last_reply.store(id, Ordering::Relaxed);
 
-        Ok(())
+        lock!(self.sequences)?.push_back(Sequence::new(id, kind));
+
+        Ok(id)
     }
 }
 
@@ -514,15 +1255,20 @@ pub enum StackMode {
     Opposite = 4,
 }
 
-impl From<u8> for StackMode {
-    fn from(value: u8) -> StackMode {
+impl TryFrom<u8> for StackMode {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<StackMode, Error> {
         match value {
-            0 => StackMode::Above,
-            1 => StackMode::Below,
-            2 => StackMode::TopIf,
-            3 => StackMode::BottomIf,
-            4 => StackMode::Opposite,
-            _ => unreachable!(),
+            0 => Ok(StackMode::Above),
+            1 => Ok(StackMode::Below),
+            2 => Ok(StackMode::TopIf),
+            3 => Ok(StackMode::BottomIf),
+            4 => Ok(StackMode::Opposite),
+            _ => Err(Error::InvalidValue {
+                name: "stack mode",
+                value: value as u32,
+            }),
         }
     }
 }
@@ -533,12 +1279,17 @@ pub enum Place {
     Bottom,
 }
 
-impl From<u8> for Place {
-    fn from(value: u8) -> Place {
+impl TryFrom<u8> for Place {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<Place, Error> {
         match value {
-            0 => Place::Top,
-            1 => Place::Bottom,
-            _ => unreachable!(),
+            0 => Ok(Place::Top),
+            1 => Ok(Place::Bottom),
+            _ => Err(Error::InvalidValue {
+                name: "place",
+                value: value as u32,
+            }),
         }
     }
 }
@@ -557,13 +1308,18 @@ pub enum WindowClass {
     InputOnly = 2,
 }
 
-impl From<u16> for WindowClass {
-    fn from(value: u16) -> WindowClass {
+impl TryFrom<u16> for WindowClass {
+    type Error = Error;
+
+    fn try_from(value: u16) -> Result<WindowClass, Error> {
         match value {
-            0 => WindowClass::CopyFromParent,
-            1 => WindowClass::InputOutput,
-            2 => WindowClass::InputOnly,
-            _ => unreachable!(),
+            0 => Ok(WindowClass::CopyFromParent),
+            1 => Ok(WindowClass::InputOutput),
+            2 => Ok(WindowClass::InputOnly),
+            _ => Err(Error::InvalidValue {
+                name: "window class",
+                value: value as u32,
+            }),
         }
     }
 }
@@ -578,16 +1334,67 @@ pub enum VisualClass {
     DirectColor = 5,
 }
 
-impl From<u8> for VisualClass {
-    fn from(value: u8) -> VisualClass {
+impl TryFrom<u8> for VisualClass {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<VisualClass, Error> {
+        match value {
+            0 => Ok(VisualClass::StaticGray),
+            1 => Ok(VisualClass::GrayScale),
+            2 => Ok(VisualClass::StaticColor),
+            3 => Ok(VisualClass::PsuedoColor),
+            4 => Ok(VisualClass::TrueColor),
+            5 => Ok(VisualClass::DirectColor),
+            _ => Err(Error::InvalidValue {
+                name: "visual class",
+                value: value as u32,
+            }),
+        }
+    }
+}
+
+/// the byte order of images and of the protocol itself
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ImageByteOrder {
+    #[default]
+    LsbFirst = 0,
+    MsbFirst = 1,
+}
+
+impl TryFrom<u8> for ImageByteOrder {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<ImageByteOrder, Error> {
         match value {
-            0 => VisualClass::StaticGray,
-            1 => VisualClass::GrayScale,
-            2 => VisualClass::StaticColor,
-            3 => VisualClass::PsuedoColor,
-            4 => VisualClass::TrueColor,
-            5 => VisualClass::DirectColor,
-            _ => unreachable!(),
+            0 => Ok(ImageByteOrder::LsbFirst),
+            1 => Ok(ImageByteOrder::MsbFirst),
+            _ => Err(Error::InvalidValue {
+                name: "image byte order",
+                value: value as u32,
+            }),
+        }
+    }
+}
+
+/// the order of the bits within each scanline unit of a bitmap
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum BitmapBitOrder {
+    #[default]
+    LeastSignificant = 0,
+    MostSignificant = 1,
+}
+
+impl TryFrom<u8> for BitmapBitOrder {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<BitmapBitOrder, Error> {
+        match value {
+            0 => Ok(BitmapBitOrder::LeastSignificant),
+            1 => Ok(BitmapBitOrder::MostSignificant),
+            _ => Err(Error::InvalidValue {
+                name: "bitmap bit order",
+                value: value as u32,
+            }),
         }
     }
 }
@@ -647,13 +1454,18 @@ pub enum EnterMode {
     Ungrab,
 }
 
-impl From<u8> for EnterMode {
-    fn from(value: u8) -> EnterMode {
+impl TryFrom<u8> for EnterMode {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<EnterMode, Error> {
         match value {
-            0 => EnterMode::Normal,
-            1 => EnterMode::Grab,
-            2 => EnterMode::Ungrab,
-            _ => unreachable!(),
+            0 => Ok(EnterMode::Normal),
+            1 => Ok(EnterMode::Grab),
+            2 => Ok(EnterMode::Ungrab),
+            _ => Err(Error::InvalidValue {
+                name: "enter mode",
+                value: value as u32,
+            }),
         }
     }
 }
@@ -750,14 +1562,19 @@ pub enum FocusMode {
     WhileGrab = 3,
 }
 
-impl From<u8> for FocusMode {
-    fn from(value: u8) -> FocusMode {
+impl TryFrom<u8> for FocusMode {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<FocusMode, Error> {
         match value {
-            0 => FocusMode::Normal,
-            1 => FocusMode::Grab,
-            2 => FocusMode::Ungrab,
-            3 => FocusMode::WhileGrab,
-            _ => unreachable!(),
+            0 => Ok(FocusMode::Normal),
+            1 => Ok(FocusMode::Grab),
+            2 => Ok(FocusMode::Ungrab),
+            3 => Ok(FocusMode::WhileGrab),
+            _ => Err(Error::InvalidValue {
+                name: "focus mode",
+                value: value as u32,
+            }),
         }
     }
 }
@@ -774,12 +1591,17 @@ pub enum PropertyState {
     Deleted,
 }
 
-impl From<u8> for PropertyState {
-    fn from(value: u8) -> PropertyState {
+impl TryFrom<u8> for PropertyState {
+    type Error = Error;
+
+    fn try_from(value: u8) -> Result<PropertyState, Error> {
         match value {
-            0 => PropertyState::NewValue,
-            1 => PropertyState::Deleted,
-            _ => unreachable!(),
+            0 => Ok(PropertyState::NewValue),
+            1 => Ok(PropertyState::Deleted),
+            _ => Err(Error::InvalidValue {
+                name: "property state",
+                value: value as u32,
+            }),
         }
     }
 }
@@ -795,8 +1617,8 @@ impl ClientMessageData {
     pub fn encode(&self) -> Vec<u8> {
         match self {
             ClientMessageData::Byte(bytes) => bytes.to_vec(),
-            ClientMessageData::Short(shorts) => request::encode(shorts).to_vec(),
-            ClientMessageData::Long(longs) => request::encode(longs).to_vec(),
+            ClientMessageData::Short(shorts) => request::encode(shorts),
+            ClientMessageData::Long(longs) => request::encode(longs),
         }
     }
 }
@@ -997,10 +1819,7 @@ impl Event {
                     type_: type_.id(),
                 };
 
-                SendEventData::new(
-                    *format,
-                    [request::encode(&event).to_vec(), data.encode()].concat(),
-                )
+                SendEventData::new(*format, [request::encode(&event), data.encode()].concat())
             }
             _ => unimplemented!("not all events are implemented for send event yet"),
         }
@@ -1040,3 +1859,333 @@ impl Event {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use crate::proto::*;
+
+    use std::fs::File;
+    use std::thread;
+
+    fn stream() -> Result<Stream, Error> {
+        let file = File::options().read(true).write(true).open("/dev/null")?;
+
+        Ok(Stream::new(
+            Arc::new(Mutex::new(file.try_clone()?)),
+            Arc::new(Mutex::new(file)),
+        ))
+    }
+
+    #[test]
+    fn test_replies_routed_by_sequence() -> Result<(), Error> {
+        let replies = Replies::new(stream()?, Arc::new(Mutex::new(Vec::new())), Signal::new());
+
+        let handles = (1..=2u64)
+            .map(|sequence| {
+                let replies = replies.clone();
+
+                thread::spawn(move || replies.wait(sequence))
+            })
+            .collect::<Vec<_>>();
+
+        replies.push(
+            2,
+            Reply::GetAtomName {
+                name: String::from("second"),
+            },
+        )?;
+        replies.push(
+            1,
+            Reply::GetAtomName {
+                name: String::from("first"),
+            },
+        )?;
+
+        let names = handles
+            .into_iter()
+            .map(|handle| match handle.join().expect("waiter panicked")? {
+                Reply::GetAtomName { name } => Ok(name),
+                _ => Err(Error::InvalidId),
+            })
+            .collect::<Result<Vec<String>, Error>>()?;
+
+        assert_eq!(names, vec![String::from("first"), String::from("second")]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_replies_error_routed_by_sequence() -> Result<(), Error> {
+        let replies = Replies::new(stream()?, Arc::new(Mutex::new(Vec::new())), Signal::new());
+
+        replies.push_sequence_error(7, Error::InvalidAtom)?;
+
+        assert!(matches!(replies.wait(7), Err(Error::InvalidAtom)));
+        assert!(replies.poll_error().is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_void_error_does_not_take_reply() -> Result<(), Error> {
+        let replies = Replies::new(stream()?, Arc::new(Mutex::new(Vec::new())), Signal::new());
+
+        let waiter = {
+            let replies = replies.clone();
+
+            thread::spawn(move || replies.wait(2))
+        };
+
+        // the error of a request without a reply arrives while another thread waits on a reply
+        replies.push_error(1, Error::InvalidId)?;
+        replies.push(
+            2,
+            Reply::GetAtomName {
+                name: String::from("reply"),
+            },
+        )?;
+
+        assert!(matches!(
+            waiter.join().expect("waiter panicked")?,
+            Reply::GetAtomName { name } if name == "reply"
+        ));
+        assert!(matches!(replies.poll_error(), Err(Error::InvalidId)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors_in_request_order() -> Result<(), Error> {
+        let replies = Replies::new(stream()?, Arc::new(Mutex::new(Vec::new())), Signal::new());
+
+        replies.push_error(3, Error::InvalidAtom)?;
+        replies.push_error(8, Error::InvalidId)?;
+
+        assert!(matches!(replies.poll_error(), Err(Error::InvalidAtom)));
+        assert!(matches!(replies.poll_error(), Err(Error::InvalidId)));
+        assert!(replies.poll_error().is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_errors_until_sequence() -> Result<(), Error> {
+        let replies = Replies::new(stream()?, Arc::new(Mutex::new(Vec::new())), Signal::new());
+
+        replies.push_error(3, Error::InvalidAtom)?;
+        replies.push_error(8, Error::InvalidId)?;
+
+        // a sync for the request with sequence 5 only sees the error of an earlier request
+        assert!(matches!(
+            replies.poll_error_until(5),
+            Err(Error::InvalidAtom)
+        ));
+        assert!(replies.poll_error_until(5).is_ok());
+        assert!(matches!(replies.poll_error_until(9), Err(Error::InvalidId)));
+        assert!(replies.poll_error().is_ok());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_close_wakes_waiters() -> Result<(), Error> {
+        let stream = stream()?;
+        let errors = Arc::new(Mutex::new(Vec::new()));
+
+        let signal = Signal::new();
+
+        let replies = Replies::new(stream.clone(), errors.clone(), signal.clone());
+        let events: Queue<u32> = Queue::new(errors, stream.closed(), signal);
+
+        let reply = {
+            let replies = replies.clone();
+
+            thread::spawn(move || replies.wait(1))
+        };
+
+        let event = {
+            let events = events.clone();
+
+            thread::spawn(move || events.wait())
+        };
+
+        events.push(1)?;
+
+        assert_eq!(event.join().expect("waiter panicked")?, 1);
+
+        let event = {
+            let events = events.clone();
+
+            thread::spawn(move || events.wait())
+        };
+
+        stream.closed().store(true, Ordering::SeqCst);
+
+        events.close()?;
+        replies.close()?;
+
+        assert!(matches!(
+            reply.join().expect("waiter panicked"),
+            Err(Error::ConnectionClosed)
+        ));
+        assert!(matches!(
+            event.join().expect("waiter panicked"),
+            Err(Error::ConnectionClosed)
+        ));
+        assert!(matches!(events.poll(), Err(Error::ConnectionClosed)));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_queue_timeout_and_errors() -> Result<(), Error> {
+        let stream = stream()?;
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let signal = Signal::new();
+
+        let replies = Replies::new(stream.clone(), errors.clone(), signal.clone());
+        let events: Queue<u32> = Queue::new(errors, stream.closed(), signal);
+
+        assert_eq!(events.try_pop()?, None);
+        assert_eq!(events.wait_timeout(Duration::from_millis(10))?, None);
+
+        events.push(1)?;
+
+        assert_eq!(events.wait_timeout(Duration::from_millis(10))?, Some(1));
+
+        // an error for a request wakes up a thread that is waiting on events
+        let waiter = {
+            let events = events.clone();
+
+            thread::spawn(move || events.wait())
+        };
+
+        replies.push_error(1, Error::InvalidAtom)?;
+
+        assert!(matches!(
+            waiter.join().expect("waiter panicked"),
+            Err(Error::InvalidAtom)
+        ));
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_wait_ready_leaves_errors() -> Result<(), Error> {
+        let stream = stream()?;
+        let errors = Arc::new(Mutex::new(Vec::new()));
+        let signal = Signal::new();
+
+        let replies = Replies::new(stream.clone(), errors.clone(), signal.clone());
+        let events: Queue<u32> = Queue::new(errors, stream.closed(), signal);
+
+        let soon = || Instant::now().checked_add(Duration::from_millis(10));
+
+        assert!(!events.wait_ready(soon())?);
+
+        let waiter = {
+            let events = events.clone();
+
+            thread::spawn(move || events.wait_ready(None))
+        };
+
+        replies.push_error(1, Error::InvalidAtom)?;
+
+        assert!(waiter.join().expect("waiter panicked")?);
+
+        // the error is left for the event functions which own it
+        assert!(events.wait_ready(soon())?);
+        assert!(matches!(events.try_pop(), Err(Error::InvalidAtom)));
+        assert!(!events.wait_ready(soon())?);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_queue_readiness() -> Result<(), Error> {
+        let stream = stream()?;
+        let events: Queue<u32> = Queue::new(
+            Arc::new(Mutex::new(Vec::new())),
+            stream.closed(),
+            Signal::new(),
+        );
+
+        events.push(1)?;
+        events.raw_fd()?;
+
+        // reading takes the byte so it is written again to keep the queue readable
+        let readable = || {
+            let readiness = events.readiness.get().expect("no readiness");
+            let readable = (&readiness.reader).read(&mut [0]).is_ok();
+
+            if readable {
+                readiness.set();
+            }
+
+            readable
+        };
+
+        assert!(readable());
+
+        events.push(2)?;
+
+        assert_eq!(events.try_pop()?, Some(1));
+        assert!(readable());
+        assert_eq!(events.try_pop()?, Some(2));
+        assert!(!readable());
+
+        events.push(3)?;
+
+        assert!(readable());
+        assert_eq!(events.try_pop()?, Some(3));
+
+        stream.closed().store(true, Ordering::SeqCst);
+        events.close()?;
+
+        assert!(readable());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_sequence_widen_wraparound() {
+        let sequence = SequenceManager::new();
+
+        assert_eq!(sequence.widen(1), 1);
+        assert_eq!(sequence.widen(0xfffe), 0xfffe);
+        assert_eq!(sequence.widen(0xfffe), 0xfffe);
+        assert_eq!(sequence.widen(3), 0x10003);
+        assert_eq!(sequence.widen(0xffff), 0x1ffff);
+        assert_eq!(sequence.widen(0), 0x20000);
+    }
+
+    #[test]
+    fn test_sequence_prune_stale() -> Result<(), Error> {
+        let sequence = SequenceManager::new();
+
+        for _ in 0..3 {
+            sequence.append(ReplyKind::GetInputFocus)?;
+        }
+
+        let stale = sequence.prune(3)?;
+
+        assert_eq!(
+            stale.iter().map(|stale| stale.id).collect::<Vec<u64>>(),
+            vec![1, 2]
+        );
+        assert_eq!(sequence.get(3)?.id, 3);
+        assert!(sequence.get(4).is_err());
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_invalid_values() {
+        assert!(matches!(EnterMode::try_from(1), Ok(EnterMode::Grab)));
+        assert!(matches!(
+            FocusMode::try_from(9),
+            Err(Error::InvalidValue { value: 9, .. })
+        ));
+        assert!(PropertyState::try_from(2).is_err());
+    }
+}
@@ -1,9 +1,15 @@
 use crate::display::error::Error;
 use crate::display::request::{self, *};
-use crate::display::xid;
-use crate::display::{Atom, Roots, Stream, Visual};
+use crate::display::xid::XidAllocator;
+use crate::display::{Atom, Connection, Roots, Stream, Visual};
 use crate::proto::*;
 
+use std::sync::Arc;
+
+use property::PropertyValue;
+
+pub mod property;
+
 /// a builder for a list of values known as `LISTofVALUE` in proto.pdf
 pub struct ValuesBuilder<T: ValueMask> {
     values: Vec<T>,
@@ -82,13 +88,11 @@ impl ValueMask for ConfigureValue {
             | ConfigureValue::Y(value)
             | ConfigureValue::Width(value)
             | ConfigureValue::Height(value)
-            | ConfigureValue::Border(value) => request::encode(&(*value as u32)).to_vec(),
+            | ConfigureValue::Border(value) => request::encode(&(*value as u32)),
 
-            ConfigureValue::Sibling(window) => request::encode(&window).to_vec(),
+            ConfigureValue::Sibling(window) => request::encode(&window),
 
-            ConfigureValue::StackMode(stack_mode) => {
-                request::encode(&(*stack_mode as u32)).to_vec()
-            }
+            ConfigureValue::StackMode(stack_mode) => request::encode(&(*stack_mode as u32)),
         }
     }
 }
@@ -151,23 +155,23 @@ impl ValueMask for WindowValue {
             | WindowValue::BorderPixel(value)
             | WindowValue::BackingPlane(value)
             | WindowValue::BackingPixel(value)
-            | WindowValue::Colormap(value) => request::encode(&(*value as u32)).to_vec(),
+            | WindowValue::Colormap(value) => request::encode(value),
 
             WindowValue::BitGravity(gravity) | WindowValue::WinGravity(gravity) => {
-                request::encode(&(*gravity as u32)).to_vec()
+                request::encode(&(*gravity as u32))
             }
 
             WindowValue::OverrideRedirect(value) | WindowValue::SaveUnder(value) => {
-                request::encode(&(*value as u32)).to_vec()
+                request::encode(&(*value as u32))
             }
 
             WindowValue::EventMask(masks) | WindowValue::DoNotPropogateMask(masks) => {
-                request::encode(&self.mask(masks)).to_vec()
+                request::encode(&self.mask(masks))
             }
 
-            WindowValue::Cursor(cursor) => request::encode(&(*cursor as u32)).to_vec(),
+            WindowValue::Cursor(cursor) => request::encode(&(*cursor as u32)),
 
-            WindowValue::BackingStore(store) => request::encode(&(*store as u32)).to_vec(),
+            WindowValue::BackingStore(store) => request::encode(&(*store as u32)),
         }
     }
 }
@@ -221,14 +225,69 @@ pub enum PropMode {
     Append = 2,
 }
 
+/// the amount of 4 byte units requested at a time when reading a whole property
+const PROPERTY_CHUNK_LENGTH: u32 = u16::MAX as u32;
+
+/// the full reply to a GetProperty request
+#[derive(Debug, Clone)]
+pub struct Property {
+    /// the actual type of the property, null if the property does not exist
+    pub type_: Atom,
+    /// the actual format of the property, 0 if the property does not exist
+    pub format: u8,
+    /// the amount of bytes of the property left after the returned value
+    pub bytes_after: u32,
+    pub value: Vec<u8>,
+}
+
+impl Property {
+    /// check if the property exists on the window
+    pub fn exists(&self) -> bool {
+        !self.type_.is_null()
+    }
+
+    fn from_reply(reply: Reply) -> Result<Property, Error> {
+        match reply {
+            Reply::GetProperty {
+                type_,
+                format,
+                bytes_after,
+                value,
+            } => Ok(Property {
+                type_,
+                format,
+                bytes_after,
+                value,
+            }),
+            _ => Err(Error::UnexpectedReply),
+        }
+    }
+
+    /// append the next chunk of a property that is read in parts
+    fn extend(&mut self, chunk: Property) -> Result<(), Error> {
+        if chunk.type_ != self.type_ || chunk.format != self.format || chunk.value.is_empty() {
+            return Err(Error::Protocol {
+                reason: String::from("property changed while it was being read"),
+            });
+        }
+
+        self.bytes_after = chunk.bytes_after;
+        self.value.extend(chunk.value);
+
+        Ok(())
+    }
+}
+
 #[derive(Clone)]
 pub struct Window {
     stream: Stream,
-    replies: Queue<Reply>,
+    replies: Replies,
     sequence: SequenceManager,
+    xid: XidAllocator,
     visual: Visual,
     depth: u8,
     id: u32,
+    connection: Arc<Connection>,
 }
 
 impl PartialEq for Window {
@@ -250,71 +309,94 @@ impl std::fmt::Debug for Window {
 }
 
 impl Window {
-    pub fn new(
+    #[allow(clippy::too_many_arguments)]
+    pub(crate) fn new(
         stream: Stream,
-        replies: Queue<Reply>,
+        replies: Replies,
         sequence: SequenceManager,
+        xid: XidAllocator,
         visual: Visual,
         depth: u8,
         id: u32,
+        connection: Arc<Connection>,
     ) -> Window {
         Window {
             stream,
             replies,
             sequence,
+            xid,
             visual,
             depth,
             id,
+            connection,
         }
     }
 
     pub(crate) fn from_id(
         stream: Stream,
-        replies: Queue<Reply>,
+        replies: Replies,
         sequence: SequenceManager,
+        xid: XidAllocator,
         roots: Roots,
         id: u32,
+        connection: Arc<Connection>,
     ) -> Result<Window, Error> {
-        sequence.append(ReplyKind::GetWindowAttributes)?;
+        let attributes = stream.send_reply_encode(
+            &sequence,
+            ReplyKind::GetWindowAttributes,
+            GetWindowAttributes {
+                opcode: Opcode::GET_WINDOW_ATTRIBUTES,
+                pad0: 0,
+                length: 2,
+                wid: id,
+            },
+        )?;
 
-        let screen = roots.first()?;
+        let geometry = stream.send_reply_encode(
+            &sequence,
+            ReplyKind::GetGeometry,
+            GetGeometry {
+                opcode: Opcode::GET_GEOMETRY,
+                pad0: 0,
+                length: 2,
+                window: id,
+            },
+        )?;
 
-        stream.send_encode(GetWindowAttributes {
-            opcode: Opcode::GET_WINDOW_ATTRIBUTES,
-            pad0: 0,
-            length: 2,
-            wid: id,
-        })?;
-
-        match replies.wait()? {
-            Reply::GetWindowAttributes(response) => Ok(Window {
-                stream,
-                replies,
-                sequence,
-                visual: roots.visual_from_id(response.visual)?,
-                depth: screen.response.root_depth,
-                id,
-            }),
-            _ => unreachable!(),
+        // wait for both replies so neither is left behind when the first one fails
+        let attributes = replies.wait(attributes);
+        let geometry = replies.wait(geometry);
+
+        match (attributes?, geometry?) {
+            (Reply::GetWindowAttributes(attributes), Reply::GetGeometry(geometry)) => {
+                // the root of the window tells which screen the window is on
+                let screen = roots.from_root(geometry.root)?;
+
+                let (depth, visual) = screen.find_visual(attributes.visual)?;
+
+                Ok(Window {
+                    stream,
+                    replies,
+                    sequence,
+                    xid,
+                    visual: visual.clone(),
+                    depth,
+                    id,
+                    connection,
+                })
+            }
+            _ => Err(Error::UnexpectedReply),
         }
     }
 
     /// check if a window property contains any of the provided atoms, the primary usage for this is checking the window type with _NET_WM_WINDOW_TYPE
-    /// NOTE: using this function in combination with a property that is not the ATOM[]/32 type will result in a undefined result
+    /// NOTE: using this function with a property that is not of the ATOM[]/32 type results in an error
 
     #[cfg(feature = "extras")]
     pub fn property_contains(&self, property: Atom, atoms: &[Atom]) -> Result<bool, Error> {
         let property = self
-            .get_property(property, Atom::ATOM, false)?
-            .map(|(mut data, _)| {
-                data.resize(data.len().max(4), 0);
-
-                data.windows(4)
-                    .map(|chunk| {
-                        Atom::new(u32::from_le_bytes([chunk[0], chunk[1], chunk[2], chunk[3]]))
-                    })
-                    .any(|value| atoms.contains(&value))
-            });
+            .get_property_value::<Vec<Atom>>(property, Atom::ATOM)?
+            .map(|values| values.iter().any(|value| atoms.contains(value)));
 
         Ok(property.unwrap_or(false))
     }
@@ -326,8 +408,6 @@ impl Window {
         event_mask: Vec<EventMask>,
         propogate: bool,
     ) -> Result<(), Error> {
-        self.sequence.skip();
-
         let request = SendEvent {
             opcode: Opcode::SEND_EVENT,
             propogate: propogate.then(|| 1).unwrap_or(0),
@@ -344,112 +424,126 @@ impl Window {
             sequence: 0,
         };
 
-        self.stream.send(
+        self.stream.send_void(
+            &self.sequence,
             &[
-                request::encode(&request).to_vec(),
-                request::encode(&generic_event).to_vec(),
+                request::encode(&request),
+                request::encode(&generic_event),
                 data.event,
             ]
             .concat(),
-        )?;
-
-        self.replies.poll_error()
+        )
     }
 
     /// get the window attributes
     pub fn get_window_attributes(&self) -> Result<GetWindowAttributesResponse, Error> {
-        self.sequence.append(ReplyKind::GetWindowAttributes)?;
-
-        self.stream.send_encode(GetWindowAttributes {
-            opcode: Opcode::GET_WINDOW_ATTRIBUTES,
-            pad0: 0,
-            length: 2,
-            wid: self.id(),
-        })?;
+        let sequence = self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::GetWindowAttributes,
+            GetWindowAttributes {
+                opcode: Opcode::GET_WINDOW_ATTRIBUTES,
+                pad0: 0,
+                length: 2,
+                wid: self.id(),
+            },
+        )?;
 
-        match self.replies.wait()? {
+        match self.replies.wait(sequence)? {
             Reply::GetWindowAttributes(response) => Ok(response),
-            _ => unreachable!(),
+            _ => Err(Error::UnexpectedReply),
         }
     }
 
     /// This request actively grabs control of the keyboard. Further key events are reported only to the
     /// grabbing client. This request overrides any active keyboard grab by this client.
-    pub fn grab_keyboard(&self, owner_events: bool, pointer_mode: PointerMode, keyboard_mode: KeyboardMode) -> Result<GrabKeyboardStatus, Error> {
-        self.sequence.append(ReplyKind::GrabKeyboard)?;
-
-        self.stream.send_encode(GrabKeyboard {
-            opcode: Opcode::GRAB_KEYBOARD,
-            owner_events: owner_events.then(|| 1).unwrap_or(0),
-            length: 4,
-            grab_window: self.id(),
-            time: 0,
-            pointer_mode: pointer_mode as u8,
-            keyboard_mode: keyboard_mode as u8,
-            pad0: 0,
-        })?;
+    pub fn grab_keyboard(
+        &self,
+        owner_events: bool,
+        pointer_mode: PointerMode,
+        keyboard_mode: KeyboardMode,
+    ) -> Result<GrabKeyboardStatus, Error> {
+        let sequence = self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::GrabKeyboard,
+            GrabKeyboard {
+                opcode: Opcode::GRAB_KEYBOARD,
+                owner_events: owner_events.then(|| 1).unwrap_or(0),
+                length: 4,
+                grab_window: self.id(),
+                time: 0,
+                pointer_mode: pointer_mode as u8,
+                keyboard_mode: keyboard_mode as u8,
+                pad0: 0,
+            },
+        )?;
 
-        match self.replies.wait()? {
+        match self.replies.wait(sequence)? {
             Reply::GrabKeyboard(status) => Ok(status),
-            _ => unreachable!(),
+            _ => Err(Error::UnexpectedReply),
         }
     }
 
     /// This request returns the root, the parent, and the children of the window. The children are listed in bottom-to-top stacking order.
     pub fn query_tree(&self) -> Result<TreeNode, Error> {
-        self.sequence.append(ReplyKind::QueryTree)?;
-
-        self.stream.send_encode(QueryTree {
-            opcode: Opcode::QUERY_TREE,
-            pad0: 0,
-            length: 2,
-            wid: self.id(),
-        })?;
+        let sequence = self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::QueryTree,
+            QueryTree {
+                opcode: Opcode::QUERY_TREE,
+                pad0: 0,
+                length: 2,
+                wid: self.id(),
+            },
+        )?;
 
-        match self.replies.wait()? {
+        match self.replies.wait(sequence)? {
             Reply::QueryTree(node) => Ok(node),
-            _ => unreachable!(),
+            _ => Err(Error::UnexpectedReply),
         }
     }
 
     /// this request adds or removes the specified window from the client’s save-set, the window must have been created by some other client (or a Match error results)
     pub fn change_save_set(&self, mode: SaveSetMode) -> Result<(), Error> {
-        self.sequence.skip();
-
-        self.stream.send_encode(ChangeSaveSet {
-            opcode: Opcode::CHANGE_SAVE_SET,
-            mode: mode.into(),
-            length: 2,
-            wid: self.id(),
-        })
+        self.stream.send_void_encode(
+            &self.sequence,
+            ChangeSaveSet {
+                opcode: Opcode::CHANGE_SAVE_SET,
+                mode: mode.into(),
+                length: 2,
+                wid: self.id(),
+            },
+        )
     }
 
     /// for RaiseLowest, `circulate` raises the lowest mapped child (if any) that is occluded by another child to the top of the stack. For LowerHighest, `circulate` lowers the highest mapped child (if any) that occludes another child to the bottom of the stack
     pub fn circulate(&self, direction: CirculateDirection) -> Result<(), Error> {
-        self.sequence.skip();
-
-        self.stream.send_encode(CirculateWindow {
-            opcode: Opcode::CIRCULATE_WINDOW,
-            direction: direction.into(),
-            length: 2,
-            wid: self.id(),
-        })
+        self.stream.send_void_encode(
+            &self.sequence,
+            CirculateWindow {
+                opcode: Opcode::CIRCULATE_WINDOW,
+                direction: direction.into(),
+                length: 2,
+                wid: self.id(),
+            },
+        )
     }
 
     /// get the geometry of the window
     pub fn get_geometry(&self) -> Result<GetGeometryResponse, Error> {
-        self.sequence.append(ReplyKind::GetGeometry)?;
-
-        self.stream.send_encode(GetGeometry {
-            opcode: Opcode::GET_GEOMETRY,
-            pad0: 0,
-            length: 2,
-            window: self.id(),
-        })?;
+        let sequence = self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::GetGeometry,
+            GetGeometry {
+                opcode: Opcode::GET_GEOMETRY,
+                pad0: 0,
+                length: 2,
+                window: self.id(),
+            },
+        )?;
 
-        match self.replies.wait()? {
+        match self.replies.wait(sequence)? {
             Reply::GetGeometry(response) => Ok(response),
-            _ => unreachable!(),
+            _ => Err(Error::UnexpectedReply),
         }
     }
 
@@ -457,18 +551,17 @@ impl Window {
 
     /// set the window to the selection owner
     pub fn set_selection_owner(&self, selection: Atom) -> Result<(), Error> {
-        self.sequence.skip();
-
-        self.stream.send_encode(SetSelectionOwner {
-            opcode: Opcode::SET_SELECTION_OWNER,
-            pad0: 0,
-            length: 4,
-            owner: self.id(),
-            selection: selection.id(),
-            time: 0,
-        })?;
-
-        self.replies.poll_error()
+        self.stream.send_void_encode(
+            &self.sequence,
+            SetSelectionOwner {
+                opcode: Opcode::SET_SELECTION_OWNER,
+                pad0: 0,
+                length: 4,
+                owner: self.id(),
+                selection: selection.id(),
+                time: 0,
+            },
+        )
     }
 
     // TODO: un-hardcode current-time
@@ -480,33 +573,31 @@ impl Window {
         target: Atom,
         property: Atom,
     ) -> Result<(), Error> {
-        self.sequence.skip();
-
-        self.stream.send_encode(ConvertSelection {
-            opcode: Opcode::CONVERT_SELECTION,
-            pad0: 0,
-            length: 6,
-            requestor: self.id(),
-            selection: selection.id(),
-            target: target.id(),
-            property: property.id(),
-            time: 0,
-        })?;
-
-        self.replies.poll_error()
+        self.stream.send_void_encode(
+            &self.sequence,
+            ConvertSelection {
+                opcode: Opcode::CONVERT_SELECTION,
+                pad0: 0,
+                length: 6,
+                requestor: self.id(),
+                selection: selection.id(),
+                target: target.id(),
+                property: property.id(),
+                time: 0,
+            },
+        )
     }
 
     fn generic_window(&self, opcode: u8, length: u16) -> Result<(), Error> {
-        self.sequence.skip();
-
-        self.stream.send_encode(GenericWindow {
-            opcode,
-            pad0: 0,
-            length,
-            wid: self.id(),
-        })?;
-
-        self.replies.poll_error()
+        self.stream.send_void_encode(
+            &self.sequence,
+            GenericWindow {
+                opcode,
+                pad0: 0,
+                length,
+                wid: self.id(),
+            },
+        )
     }
 
     /// window id
@@ -526,10 +617,8 @@ impl Window {
 
     /// create a child window with provided window arguments
     pub fn create_window(&self, mut window: WindowArguments) -> Result<Window, Error> {
-        self.sequence.skip();
-
         let window_values_request = window.values.build();
-        let wid = xid::next()?;
+        let wid = self.xid.next()?;
 
         let request = CreateWindow {
             opcode: Opcode::CREATE_WINDOW,
@@ -547,87 +636,85 @@ impl Window {
             value_mask: window.values.mask,
         };
 
-        self.stream
-            .send(&[window_values_request, request::encode(&request).to_vec()].concat())?;
-
-        self.replies.poll_error()?;
+        self.stream.send_void(
+            &self.sequence,
+            &[request::encode(&request), window_values_request].concat(),
+        )?;
 
         Ok(Window::new(
             self.stream.clone(),
             self.replies.clone(),
             self.sequence.clone(),
+            self.xid.clone(),
             window.visual,
             window.depth,
             wid,
+            self.connection.clone(),
         ))
     }
 
     /// kill the window
     pub fn kill(&self) -> Result<(), Error> {
-        self.sequence.skip();
-
-        self.stream.send_encode(KillClient {
-            opcode: Opcode::KILL_CLIENT,
-            pad0: 0,
-            length: 2,
-            resource: self.id(),
-        })?;
-
-        self.replies.poll_error()
+        self.stream.send_void_encode(
+            &self.sequence,
+            KillClient {
+                opcode: Opcode::KILL_CLIENT,
+                pad0: 0,
+                length: 2,
+                resource: self.id(),
+            },
+        )
     }
 
     /// sets the current input focus to the window
     pub fn set_input_focus(&self, revert_to: RevertTo) -> Result<(), Error> {
-        self.sequence.skip();
-
-        self.stream.send_encode(SetInputFocus {
-            opcode: Opcode::SET_INPUT_FOCUS,
-            revert_to: revert_to as u8,
-            length: 3,
-            focus: self.id(),
-            time: 0,
-        })?;
-
-        self.replies.poll_error()
+        self.stream.send_void_encode(
+            &self.sequence,
+            SetInputFocus {
+                opcode: Opcode::SET_INPUT_FOCUS,
+                revert_to: revert_to as u8,
+                length: 3,
+                focus: self.id(),
+                time: 0,
+            },
+        )
     }
 
     /// change the attributes of a window
     pub fn change_attributes(&self, mut values: ValuesBuilder<WindowValue>) -> Result<(), Error> {
-        self.sequence.skip();
-
         let request = values.build();
 
-        self.stream.send_encode(ChangeWindowAttributes {
+        let header = ChangeWindowAttributes {
             opcode: Opcode::CHANGE_WINDOW_ATTRIBUTES,
             pad0: 0,
             length: values.len() + 3,
             wid: self.id(),
             mask: values.mask,
-        })?;
-
-        self.stream.send(&request)?;
+        };
 
-        self.replies.poll_error()
+        self.stream.send_void(
+            &self.sequence,
+            &[request::encode(&header).as_slice(), &request].concat(),
+        )
     }
 
     /// configure the window
     pub fn configure(&self, mut values: ValuesBuilder<ConfigureValue>) -> Result<(), Error> {
-        self.sequence.skip();
-
         let request = values.build();
 
-        self.stream.send_encode(ConfigureWindow {
+        let header = ConfigureWindow {
             opcode: Opcode::CONFIGURE_WINDOW,
             pad0: 0,
             length: values.len() + 3,
             wid: self.id(),
             mask: values.mask as u16,
             pad1: 0,
-        })?;
-
-        self.stream.send(&request)?;
+        };
 
-        self.replies.poll_error()
+        self.stream.send_void(
+            &self.sequence,
+            &[request::encode(&header).as_slice(), &request].concat(),
+        )
     }
 
     /// set the border of a window to a pixel
@@ -689,19 +776,18 @@ impl Window {
 
     /// become the child of a parent window
     pub fn reparent(&self, parent: Window, x: u16, y: u16) -> Result<(), Error> {
-        self.sequence.skip();
-
-        self.stream.send_encode(ReparentWindow {
-            opcode: Opcode::REPARENT_WINDOW,
-            pad0: 0,
-            length: 4,
-            window: self.id(),
-            parent: parent.id(),
-            x,
-            y,
-        })?;
-
-        self.replies.poll_error()
+        self.stream.send_void_encode(
+            &self.sequence,
+            ReparentWindow {
+                opcode: Opcode::REPARENT_WINDOW,
+                pad0: 0,
+                length: 4,
+                window: self.id(),
+                parent: parent.id(),
+                x,
+                y,
+            },
+        )
     }
 
     /// destroy the current window object
@@ -734,12 +820,11 @@ impl Window {
         mode: PropMode,
         data: &[u8],
     ) -> Result<(), Error> {
-        self.sequence.skip();
-
         let request = ChangeProperty {
             opcode: Opcode::CHANGE_PROPERTY,
             mode: mode as u8,
-            length: 6 + (data.len() as u16 + request::pad(data.len()) as u16) / 4,
+            // filled in by the stream, the data may need a big request
+            length: 0,
             window: self.id(),
             property: property.id(),
             type_: type_.id(),
@@ -748,22 +833,19 @@ impl Window {
             data_len: format.encode(data.len()),
         };
 
-        self.stream.send(
+        self.stream.send_void(
+            &self.sequence,
             &[
-                request::encode(&request),
+                request::encode(&request).as_slice(),
                 data,
                 &vec![0u8; request::pad(data.len())],
             ]
             .concat(),
-        )?;
-
-        self.replies.poll_error()
+        )
     }
 
     /// delete a property from a window
     pub fn delete_property(&self, property: Atom) -> Result<(), Error> {
-        self.sequence.skip();
-
         let request = GenericWindow {
             opcode: Opcode::DELETE_PROPERTY,
             pad0: 0,
@@ -771,10 +853,46 @@ impl Window {
             wid: self.id(),
         };
 
-        self.stream
-            .send(&[request::encode(&request), request::encode(&property.id())].concat())?;
+        self.stream.send_void(
+            &self.sequence,
+            &[request::encode(&request), request::encode(&property.id())].concat(),
+        )
+    }
 
-        self.replies.poll_error()
+    /// set a property of a window to a typed value, the format is taken from the value
+    pub fn set_property<T: PropertyValue>(
+        &self,
+        property: Atom,
+        type_: Atom,
+        value: &T,
+    ) -> Result<(), Error> {
+        let mut data = Vec::new();
+
+        value.encode(type_, &mut data)?;
+
+        self.change_property(property, type_, T::FORMAT, PropMode::Replace, &data)
+    }
+
+    /// get the typed value of a property from a window, the type and format of the property must
+    /// match the requested type and the format of the value
+    pub fn get_property_value<T: PropertyValue>(
+        &self,
+        property: Atom,
+        type_: Atom,
+    ) -> Result<Option<T>, Error> {
+        match self.get_full_property(property, type_, false)? {
+            Some(property)
+                if (type_ == Atom::ANY_PROPERTY_TYPE || property.type_ == type_)
+                    && property.format == T::FORMAT as u8 =>
+            {
+                T::decode(property.type_, &property.value).map(Some)
+            }
+            Some(property) => Err(Error::PropertyMismatch {
+                type_: property.type_.id(),
+                format: property.format,
+            }),
+            None => Ok(None),
+        }
     }
 
     /// get the value of a property from a window
@@ -784,42 +902,148 @@ impl Window {
         type_: Atom,
         delete: bool,
     ) -> Result<Option<(Vec<u8>, Atom)>, Error> {
-        self.sequence.append(ReplyKind::GetProperty)?;
+        Ok(self
+            .get_full_property(property, type_, delete)?
+            .map(|property| (property.value, property.type_)))
+    }
 
-        self.stream.send_encode(GetProperty {
-            opcode: Opcode::GET_PROPERTY,
-            delete: delete.then(|| 1).unwrap_or(0),
-            length: 6,
-            window: self.id(),
-            property: property.id(),
-            type_: type_.id(),
-            long_offset: u32::MIN,
-            long_length: u16::MAX as u32,
-        })?;
-
-        match self.replies.wait()? {
-            Reply::GetProperty { type_, value } => Ok(type_
-                .is_null()
-                .then(|| None)
-                .unwrap_or(Some((value, type_)))),
-            _ => unreachable!(),
+    /// get the whole value of a property from a window, properties larger than a single reply
+    /// are read in chunks, when deleting the property is only deleted once all of it has been read
+    pub fn get_full_property(
+        &self,
+        property: Atom,
+        type_: Atom,
+        delete: bool,
+    ) -> Result<Option<Property>, Error> {
+        let mut full =
+            self.get_property_range(property, type_, delete, 0, PROPERTY_CHUNK_LENGTH)?;
+
+        // the value is empty when the type does not match, in which case no progress can be made
+        while full.bytes_after > 0 && !full.value.is_empty() {
+            let offset = (full.value.len() / 4) as u32;
+
+            let chunk =
+                self.get_property_range(property, type_, delete, offset, PROPERTY_CHUNK_LENGTH)?;
+
+            full.extend(chunk)?;
+        }
+
+        Ok(full.exists().then_some(full))
+    }
+
+    /// get the value of a property from a window without blocking the executor
+    #[cfg(feature = "async")]
+    pub async fn get_property_async(
+        &self,
+        property: Atom,
+        type_: Atom,
+        delete: bool,
+    ) -> Result<Option<(Vec<u8>, Atom)>, Error> {
+        Ok(self
+            .get_full_property_async(property, type_, delete)
+            .await?
+            .map(|property| (property.value, property.type_)))
+    }
+
+    /// get the whole value of a property from a window without blocking the executor, this
+    /// reads the property in chunks like [`Window::get_full_property`]
+    #[cfg(feature = "async")]
+    pub async fn get_full_property_async(
+        &self,
+        property: Atom,
+        type_: Atom,
+        delete: bool,
+    ) -> Result<Option<Property>, Error> {
+        let mut full = self
+            .get_property_range_async(property, type_, delete, 0, PROPERTY_CHUNK_LENGTH)
+            .await?;
+
+        while full.bytes_after > 0 && !full.value.is_empty() {
+            let offset = (full.value.len() / 4) as u32;
+
+            let chunk = self
+                .get_property_range_async(property, type_, delete, offset, PROPERTY_CHUNK_LENGTH)
+                .await?;
+
+            full.extend(chunk)?;
         }
+
+        Ok(full.exists().then_some(full))
+    }
+
+    /// get part of a property from a window without blocking the executor
+    #[cfg(feature = "async")]
+    pub async fn get_property_range_async(
+        &self,
+        property: Atom,
+        type_: Atom,
+        delete: bool,
+        offset: u32,
+        length: u32,
+    ) -> Result<Property, Error> {
+        let sequence = self.send_get_property(property, type_, delete, offset, length)?;
+
+        let reply =
+            crate::display::future::ReplyFuture::new(self.replies.clone(), sequence).await?;
+
+        Property::from_reply(reply)
+    }
+
+    /// get part of a property from a window, the offset and length are in units of 4 bytes,
+    /// the reply is returned as is even when the property does not exist
+    pub fn get_property_range(
+        &self,
+        property: Atom,
+        type_: Atom,
+        delete: bool,
+        offset: u32,
+        length: u32,
+    ) -> Result<Property, Error> {
+        let sequence = self.send_get_property(property, type_, delete, offset, length)?;
+
+        Property::from_reply(self.replies.wait(sequence)?)
+    }
+
+    fn send_get_property(
+        &self,
+        property: Atom,
+        type_: Atom,
+        delete: bool,
+        offset: u32,
+        length: u32,
+    ) -> Result<u64, Error> {
+        self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::GetProperty,
+            GetProperty {
+                opcode: Opcode::GET_PROPERTY,
+                delete: delete.then(|| 1).unwrap_or(0),
+                length: 6,
+                window: self.id(),
+                property: property.id(),
+                type_: type_.id(),
+                long_offset: offset,
+                long_length: length,
+            },
+        )
     }
 
     /// get info about the pointer such as position
     pub fn query_pointer(&self) -> Result<QueryPointerResponse, Error> {
-        self.sequence.append(ReplyKind::QueryPointer)?;
-
-        self.stream.send_encode(QueryPointer {
-            opcode: Opcode::QUERY_POINTER,
-            pad0: 0,
-            length: 2,
-            wid: self.id(),
-        })?;
+        let sequence = self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::QueryPointer,
+            QueryPointer {
+                opcode: Opcode::QUERY_POINTER,
+                pad0: 0,
+                length: 2,
+                wid: self.id(),
+            },
+        )?;
 
-        match self.replies.wait()? {
+        match self.replies.wait(sequence)? {
             Reply::QueryPointer(response) => Ok(response),
-            _ => unreachable!(),
+            _ => Err(Error::UnexpectedReply),
         }
     }
 
@@ -833,23 +1057,22 @@ impl Window {
         keyboard_mode: KeyboardMode,
         owner_events: bool,
     ) -> Result<(), Error> {
-        self.sequence.skip();
-
-        self.stream.send_encode(GrabKey {
-            opcode: Opcode::GRAB_KEY,
-            owner_events: owner_events.then(|| 1).unwrap_or(0),
-            length: 4,
-            grab_window: self.id(),
-            modifiers: modifiers
-                .iter()
-                .fold(0, |acc, modifier| acc | *modifier as u16),
-            key: keycode,
-            pointer_mode: pointer_mode as u8,
-            keyboard_mode: keyboard_mode as u8,
-            pad0: [0u8; 3],
-        })?;
-
-        self.replies.poll_error()
+        self.stream.send_void_encode(
+            &self.sequence,
+            GrabKey {
+                opcode: Opcode::GRAB_KEY,
+                owner_events: owner_events.then(|| 1).unwrap_or(0),
+                length: 4,
+                grab_window: self.id(),
+                modifiers: modifiers
+                    .iter()
+                    .fold(0, |acc, modifier| acc | *modifier as u16),
+                key: keycode,
+                pointer_mode: pointer_mode as u8,
+                keyboard_mode: keyboard_mode as u8,
+                pad0: [0u8; 3],
+            },
+        )
     }
 
     /// grab a button from the window,
@@ -865,45 +1088,43 @@ impl Window {
         owner_events: bool,
         confine_to: u32,
     ) -> Result<(), Error> {
-        self.sequence.skip();
-
-        self.stream.send_encode(GrabButton {
-            opcode: Opcode::GRAB_BUTTON,
-            owner_events: owner_events.then(|| 1).unwrap_or(0),
-            length: 6,
-            grab_window: self.id(),
-            event_mask: event_mask.iter().fold(0, |acc, mask| acc | *mask as u16),
-            pointer_mode: pointer_mode as u8,
-            keyboard_mode: keyboard_mode as u8,
-            confine_to,
-            cursor: cursor as u32,
-            button: button as u8,
-            pad0: 0,
-            modifiers: modifiers
-                .iter()
-                .fold(0, |acc, modifier| acc | *modifier as u16),
-        })?;
-
-        self.replies.poll_error()
+        self.stream.send_void_encode(
+            &self.sequence,
+            GrabButton {
+                opcode: Opcode::GRAB_BUTTON,
+                owner_events: owner_events.then(|| 1).unwrap_or(0),
+                length: 6,
+                grab_window: self.id(),
+                event_mask: event_mask.iter().fold(0, |acc, mask| acc | *mask as u16),
+                pointer_mode: pointer_mode as u8,
+                keyboard_mode: keyboard_mode as u8,
+                confine_to,
+                cursor: cursor as u32,
+                button: button as u8,
+                pad0: 0,
+                modifiers: modifiers
+                    .iter()
+                    .fold(0, |acc, modifier| acc | *modifier as u16),
+            },
+        )
     }
 
     /// ungrab a button from the window,
     /// buttons are not valid modifiers
     pub fn ungrab_button(&self, button: Button, modifiers: Vec<KeyMask>) -> Result<(), Error> {
-        self.sequence.skip();
-
-        self.stream.send_encode(UngrabButton {
-            opcode: Opcode::UNGRAB_BUTTON,
-            button: button as u8,
-            length: 3,
-            grab_window: self.id(),
-            modifiers: modifiers
-                .iter()
-                .fold(0, |acc, modifier| acc | *modifier as u16),
-            pad0: [0u8; 2],
-        })?;
-
-        self.replies.poll_error()
+        self.stream.send_void_encode(
+            &self.sequence,
+            UngrabButton {
+                opcode: Opcode::UNGRAB_BUTTON,
+                button: button as u8,
+                length: 3,
+                grab_window: self.id(),
+                modifiers: modifiers
+                    .iter()
+                    .fold(0, |acc, modifier| acc | *modifier as u16),
+                pad0: [0u8; 2],
+            },
+        )
     }
 
     /// grab the pointer
@@ -916,26 +1137,28 @@ impl Window {
         owner_events: bool,
         confine_to: u32,
     ) -> Result<(), Error> {
-        self.sequence.append(ReplyKind::GrabPointer)?;
-
         // TODO: un-hardcode time as current time
 
-        self.stream.send_encode(GrabPointer {
-            opcode: Opcode::GRAB_POINTER,
-            owner_events: owner_events.then(|| 1).unwrap_or(0),
-            length: 6,
-            grab_window: self.id(),
-            event_mask: event_mask.iter().fold(0, |acc, mask| acc | *mask as u16),
-            pointer_mode: pointer_mode as u8,
-            keyboard_mode: keyboard_mode as u8,
-            confine_to,
-            cursor: cursor as u32,
-            time: 0,
-        })?;
-
-        match self.replies.wait()? {
+        let sequence = self.stream.send_reply_encode(
+            &self.sequence,
+            ReplyKind::GrabPointer,
+            GrabPointer {
+                opcode: Opcode::GRAB_POINTER,
+                owner_events: owner_events.then(|| 1).unwrap_or(0),
+                length: 6,
+                grab_window: self.id(),
+                event_mask: event_mask.iter().fold(0, |acc, mask| acc | *mask as u16),
+                pointer_mode: pointer_mode as u8,
+                keyboard_mode: keyboard_mode as u8,
+                confine_to,
+                cursor: cursor as u32,
+                time: 0,
+            },
+        )?;
+
+        match self.replies.wait(sequence)? {
             Reply::GrabPointer(_) => Ok(()),
-            _ => unreachable!(),
+            _ => Err(Error::UnexpectedReply),
         }
     }
 }
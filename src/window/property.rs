@@ -0,0 +1,437 @@
+use crate::display::error::Error;
+use crate::display::Atom;
+
+use super::PropFormat;
+
+/// a value that can be stored in a window property
+///
+/// window ids are stored as [`WindowId`] with the WINDOW type, strings are stored as latin-1
+/// with the STRING type and as utf-8 with any other type such as UTF8_STRING
+pub trait PropertyValue: Sized {
+    /// the format the value is stored with
+    const FORMAT: PropFormat;
+
+    /// append the value to the data of a property with the type
+    fn encode(&self, type_: Atom, data: &mut Vec<u8>) -> Result<(), Error>;
+
+    /// read the value from the data of a property with the type
+    fn decode(type_: Atom, data: &[u8]) -> Result<Self, Error>;
+}
+
+/// a property value with a fixed size in bytes, a list of these can be stored in one property
+pub trait FixedPropertyValue: PropertyValue {
+    const SIZE: usize;
+}
+
+/// decode a fixed size value, the data must be exactly as long as the value
+pub(crate) fn decode_exact<const N: usize>(data: &[u8]) -> Result<[u8; N], Error> {
+    data.try_into().map_err(|_| Error::Protocol {
+        reason: format!("property value is {} bytes, expected {}", data.len(), N),
+    })
+}
+
+macro_rules! primitive {
+    ($($type:ty => $format:expr),*) => {
+        $(
+            impl PropertyValue for $type {
+                const FORMAT: PropFormat = $format;
+
+                fn encode(&self, _type_: Atom, data: &mut Vec<u8>) -> Result<(), Error> {
+                    data.extend_from_slice(&self.to_le_bytes());
+
+                    Ok(())
+                }
+
+                fn decode(_type_: Atom, data: &[u8]) -> Result<Self, Error> {
+                    Ok(<$type>::from_le_bytes(decode_exact(data)?))
+                }
+            }
+
+            impl FixedPropertyValue for $type {
+                const SIZE: usize = std::mem::size_of::<$type>();
+            }
+        )*
+    };
+}
+
+primitive!(
+    u8 => PropFormat::Format8,
+    i8 => PropFormat::Format8,
+    u16 => PropFormat::Format16,
+    i16 => PropFormat::Format16,
+    u32 => PropFormat::Format32,
+    i32 => PropFormat::Format32
+);
+
+impl PropertyValue for Atom {
+    const FORMAT: PropFormat = PropFormat::Format32;
+
+    fn encode(&self, _type_: Atom, data: &mut Vec<u8>) -> Result<(), Error> {
+        data.extend_from_slice(&self.to_le_bytes());
+
+        Ok(())
+    }
+
+    fn decode(_type_: Atom, data: &[u8]) -> Result<Self, Error> {
+        Ok(Atom::from_le_bytes(decode_exact(data)?))
+    }
+}
+
+impl FixedPropertyValue for Atom {
+    const SIZE: usize = 4;
+}
+
+/// the id of a window stored in a property, such as the WINDOW type used by WM_TRANSIENT_FOR
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct WindowId(pub u32);
+
+impl PropertyValue for WindowId {
+    const FORMAT: PropFormat = PropFormat::Format32;
+
+    fn encode(&self, type_: Atom, data: &mut Vec<u8>) -> Result<(), Error> {
+        self.0.encode(type_, data)
+    }
+
+    fn decode(type_: Atom, data: &[u8]) -> Result<Self, Error> {
+        u32::decode(type_, data).map(WindowId)
+    }
+}
+
+impl FixedPropertyValue for WindowId {
+    const SIZE: usize = 4;
+}
+
+/// the STRING type is latin-1 which maps every byte to the unicode character with the same value
+impl PropertyValue for String {
+    const FORMAT: PropFormat = PropFormat::Format8;
+
+    fn encode(&self, type_: Atom, data: &mut Vec<u8>) -> Result<(), Error> {
+        if type_ != Atom::STRING {
+            data.extend_from_slice(self.as_bytes());
+
+            return Ok(());
+        }
+
+        for character in self.chars() {
+            let byte = u8::try_from(character).map_err(|_| Error::InvalidValue {
+                name: "latin-1 character",
+                value: character as u32,
+            })?;
+
+            data.push(byte);
+        }
+
+        Ok(())
+    }
+
+    fn decode(type_: Atom, data: &[u8]) -> Result<Self, Error> {
+        if type_ == Atom::STRING {
+            Ok(data.iter().map(|byte| char::from(*byte)).collect())
+        } else {
+            String::from_utf8(data.to_vec()).map_err(|_| Error::Utf8)
+        }
+    }
+}
+
+impl<T: FixedPropertyValue> PropertyValue for Vec<T> {
+    const FORMAT: PropFormat = T::FORMAT;
+
+    fn encode(&self, type_: Atom, data: &mut Vec<u8>) -> Result<(), Error> {
+        self.iter().try_for_each(|value| value.encode(type_, data))
+    }
+
+    fn decode(type_: Atom, data: &[u8]) -> Result<Self, Error> {
+        if data.len() % T::SIZE != 0 {
+            return Err(Error::Protocol {
+                reason: format!(
+                    "property list of {} bytes is not a multiple of {}",
+                    data.len(),
+                    T::SIZE
+                ),
+            });
+        }
+
+        data.chunks(T::SIZE)
+            .map(|chunk| T::decode(type_, chunk))
+            .collect()
+    }
+}
+
+/// a list of strings is stored with each string followed by a null byte
+impl PropertyValue for Vec<String> {
+    const FORMAT: PropFormat = PropFormat::Format8;
+
+    fn encode(&self, type_: Atom, data: &mut Vec<u8>) -> Result<(), Error> {
+        for string in self {
+            string.encode(type_, data)?;
+            data.push(0);
+        }
+
+        Ok(())
+    }
+
+    fn decode(type_: Atom, data: &[u8]) -> Result<Self, Error> {
+        data.strip_suffix(&[0])
+            .unwrap_or(data)
+            .split(|byte| *byte == 0)
+            .filter(|_| !data.is_empty())
+            .map(|string| String::decode(type_, string))
+            .collect()
+    }
+}
+
+/// read a list of 32 bit values that may be shorter than the full structure, older clients leave
+/// out the fields that were added to the icccm later
+fn decode_fields<const N: usize>(
+    type_: Atom,
+    data: &[u8],
+    minimum: usize,
+) -> Result<[u32; N], Error> {
+    let values = Vec::<u32>::decode(type_, data)?;
+
+    if values.len() < minimum || values.len() > N {
+        return Err(Error::Protocol {
+            reason: format!(
+                "property has {} fields, expected {} to {}",
+                values.len(),
+                minimum,
+                N
+            ),
+        });
+    }
+
+    let mut fields = [0; N];
+
+    fields[..values.len()].copy_from_slice(&values);
+
+    Ok(fields)
+}
+
+/// the WM_HINTS property, <https://x.org/releases/X11R7.6/doc/xorg-docs/specs/ICCCM/icccm.html#wm_hints_property>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WmHints {
+    pub flags: u32,
+    pub input: bool,
+    pub initial_state: u32,
+    pub icon_pixmap: u32,
+    pub icon_window: u32,
+    pub icon_x: i32,
+    pub icon_y: i32,
+    pub icon_mask: u32,
+    pub window_group: u32,
+}
+
+impl PropertyValue for WmHints {
+    const FORMAT: PropFormat = PropFormat::Format32;
+
+    fn encode(&self, type_: Atom, data: &mut Vec<u8>) -> Result<(), Error> {
+        vec![
+            self.flags,
+            self.input as u32,
+            self.initial_state,
+            self.icon_pixmap,
+            self.icon_window,
+            self.icon_x as u32,
+            self.icon_y as u32,
+            self.icon_mask,
+            self.window_group,
+        ]
+        .encode(type_, data)
+    }
+
+    fn decode(type_: Atom, data: &[u8]) -> Result<Self, Error> {
+        // the window group was added last so some clients only store 8 fields
+        let fields = decode_fields::<9>(type_, data, 8)?;
+
+        Ok(WmHints {
+            flags: fields[0],
+            input: fields[1] != 0,
+            initial_state: fields[2],
+            icon_pixmap: fields[3],
+            icon_window: fields[4],
+            icon_x: fields[5] as i32,
+            icon_y: fields[6] as i32,
+            icon_mask: fields[7],
+            window_group: fields[8],
+        })
+    }
+}
+
+/// the WM_SIZE_HINTS type used by WM_NORMAL_HINTS, the aspect ratios are stored as numerator and
+/// denominator, <https://x.org/releases/X11R7.6/doc/xorg-docs/specs/ICCCM/icccm.html#wm_normal_hints_property>
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub struct WmSizeHints {
+    pub flags: u32,
+    pub x: i32,
+    pub y: i32,
+    pub width: i32,
+    pub height: i32,
+    pub min_width: i32,
+    pub min_height: i32,
+    pub max_width: i32,
+    pub max_height: i32,
+    pub width_inc: i32,
+    pub height_inc: i32,
+    pub min_aspect: (i32, i32),
+    pub max_aspect: (i32, i32),
+    pub base_width: i32,
+    pub base_height: i32,
+    pub win_gravity: u32,
+}
+
+impl PropertyValue for WmSizeHints {
+    const FORMAT: PropFormat = PropFormat::Format32;
+
+    fn encode(&self, type_: Atom, data: &mut Vec<u8>) -> Result<(), Error> {
+        vec![
+            self.flags,
+            self.x as u32,
+            self.y as u32,
+            self.width as u32,
+            self.height as u32,
+            self.min_width as u32,
+            self.min_height as u32,
+            self.max_width as u32,
+            self.max_height as u32,
+            self.width_inc as u32,
+            self.height_inc as u32,
+            self.min_aspect.0 as u32,
+            self.min_aspect.1 as u32,
+            self.max_aspect.0 as u32,
+            self.max_aspect.1 as u32,
+            self.base_width as u32,
+            self.base_height as u32,
+            self.win_gravity,
+        ]
+        .encode(type_, data)
+    }
+
+    fn decode(type_: Atom, data: &[u8]) -> Result<Self, Error> {
+        // the base size and gravity were added in icccm 1.0 so older clients only store 15 fields
+        let fields = decode_fields::<18>(type_, data, 15)?.map(|field| field as i32);
+
+        Ok(WmSizeHints {
+            flags: fields[0] as u32,
+            x: fields[1],
+            y: fields[2],
+            width: fields[3],
+            height: fields[4],
+            min_width: fields[5],
+            min_height: fields[6],
+            max_width: fields[7],
+            max_height: fields[8],
+            width_inc: fields[9],
+            height_inc: fields[10],
+            min_aspect: (fields[11], fields[12]),
+            max_aspect: (fields[13], fields[14]),
+            base_width: fields[15],
+            base_height: fields[16],
+            win_gravity: fields[17] as u32,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roundtrip<T: PropertyValue>(value: &T) -> Result<T, Error> {
+        roundtrip_as(Atom::CARDINAL, value)
+    }
+
+    fn roundtrip_as<T: PropertyValue>(type_: Atom, value: &T) -> Result<T, Error> {
+        let mut data = Vec::new();
+
+        value.encode(type_, &mut data)?;
+
+        T::decode(type_, &data)
+    }
+
+    #[test]
+    fn test_property_values() -> Result<(), Error> {
+        assert_eq!(roundtrip(&0xdeadbeefu32)?, 0xdeadbeef);
+        assert_eq!(roundtrip(&-42i32)?, -42);
+        assert_eq!(roundtrip(&Atom::WINDOW)?, Atom::WINDOW);
+        assert_eq!(roundtrip(&vec![1u32, 2, 3])?, vec![1, 2, 3]);
+        assert_eq!(roundtrip(&String::from("yaxi"))?, "yaxi");
+
+        let strings = vec![String::from("one"), String::new(), String::from("three")];
+
+        assert_eq!(roundtrip(&strings)?, strings);
+        assert_eq!(roundtrip(&Vec::<String>::new())?, Vec::<String>::new());
+
+        assert!(u32::decode(Atom::CARDINAL, &[0; 3]).is_err());
+        assert!(Vec::<u32>::decode(Atom::CARDINAL, &[0; 6]).is_err());
+        assert_eq!(roundtrip(&vec![WindowId(7)])?, vec![WindowId(7)]);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_string_encodings() -> Result<(), Error> {
+        let title = String::from("café");
+
+        // STRING is latin-1 so every character is a single byte
+        let mut data = Vec::new();
+
+        title.encode(Atom::STRING, &mut data)?;
+
+        assert_eq!(data, b"caf\xe9");
+        assert_eq!(String::decode(Atom::STRING, &data)?, title);
+        assert_eq!(roundtrip_as(Atom::STRING, &title)?, title);
+
+        // any other type such as UTF8_STRING is utf-8
+        assert_eq!(roundtrip(&title)?, title);
+        assert!(String::decode(Atom::CARDINAL, &[0xe9]).is_err());
+
+        assert!(String::from("π").encode(Atom::STRING, &mut data).is_err());
+
+        let strings = vec![String::from("ünïcode"), String::from("latin")];
+
+        assert_eq!(roundtrip_as(Atom::STRING, &strings)?, strings);
+
+        Ok(())
+    }
+
+    #[test]
+    fn test_icccm_hints() -> Result<(), Error> {
+        let hints = WmHints {
+            flags: 1 | 2,
+            input: true,
+            initial_state: 1,
+            icon_x: -4,
+            window_group: 0x0040_0001,
+            ..WmHints::default()
+        };
+
+        assert_eq!(roundtrip_as(Atom::WM_HINTS, &hints)?, hints);
+
+        let size_hints = WmSizeHints {
+            flags: 16 | 32,
+            min_width: 100,
+            min_height: 50,
+            max_width: -1,
+            min_aspect: (4, 3),
+            max_aspect: (16, 9),
+            win_gravity: 10,
+            ..WmSizeHints::default()
+        };
+
+        assert_eq!(roundtrip_as(Atom::WM_SIZE_HINTS, &size_hints)?, size_hints);
+
+        // hints from older clients leave out the fields that were added later
+        let data = [0u8; 15 * 4];
+
+        assert_eq!(
+            WmSizeHints::decode(Atom::WM_SIZE_HINTS, &data)?,
+            WmSizeHints::default()
+        );
+        assert_eq!(
+            WmHints::decode(Atom::WM_HINTS, &[0u8; 8 * 4])?,
+            WmHints::default()
+        );
+        assert!(WmHints::decode(Atom::WM_HINTS, &[0u8; 4 * 4]).is_err());
+
+        Ok(())
+    }
+}
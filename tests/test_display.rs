@@ -0,0 +1,298 @@
+use yaxi::display::{self, Atom};
+use yaxi::proto::{EventMask, WindowClass};
+use yaxi::window::{PropFormat, PropMode, ValuesBuilder, WindowArguments, WindowKind};
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use serial_test::serial;
+
+    #[test]
+    #[serial]
+    fn test_display_sync() {
+        let display = display::open(None).unwrap();
+
+        let root = display.default_root_window().unwrap();
+
+        let result = root.select_input(&[]);
+        assert!(result.is_ok());
+
+        assert!(display.flush().is_ok());
+        assert!(display.sync().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn test_display_sync_reports_errors() {
+        let display = display::open(None).unwrap();
+
+        let root = display.default_root_window().unwrap();
+
+        let window = root
+            .create_window(WindowArguments {
+                depth: root.depth(),
+                x: 0,
+                y: 0,
+                width: 10,
+                height: 10,
+                border_width: 0,
+                class: WindowClass::InputOutput,
+                visual: root.visual(),
+                values: ValuesBuilder::new(vec![]),
+            })
+            .unwrap();
+
+        let destroyed = window.clone();
+
+        assert!(window.destroy(WindowKind::Window).is_ok());
+        assert!(display.sync().is_ok());
+
+        // the window no longer exists so the server responds with an error
+        assert!(destroyed.map(WindowKind::Window).is_ok());
+        assert!(display.sync().is_err());
+        assert!(display.sync().is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn test_get_full_property() {
+        let display = display::open(None).unwrap();
+
+        let root = display.default_root_window().unwrap();
+
+        let window = root
+            .create_window(WindowArguments {
+                depth: root.depth(),
+                x: 0,
+                y: 0,
+                width: 10,
+                height: 10,
+                border_width: 0,
+                class: WindowClass::InputOutput,
+                visual: root.visual(),
+                values: ValuesBuilder::new(vec![]),
+            })
+            .unwrap();
+
+        let property = display.intern_atom("YAXI_TEST_PROPERTY", false).unwrap();
+
+        // larger than a single 256KiB reply
+        let data: Vec<u8> = (0..300 * 1024).map(|i| (i % 251) as u8).collect();
+
+        window
+            .change_property(
+                property,
+                Atom::STRING,
+                PropFormat::Format8,
+                PropMode::Replace,
+                &data,
+            )
+            .unwrap();
+
+        let partial = window
+            .get_property_range(property, Atom::STRING, false, 1, 2)
+            .unwrap();
+
+        assert_eq!(partial.format, 8);
+        assert_eq!(partial.value, data[4..12]);
+        assert_eq!(partial.bytes_after as usize, data.len() - 12);
+
+        let full = window
+            .get_full_property(property, Atom::STRING, true)
+            .unwrap()
+            .unwrap();
+
+        assert_eq!(full.type_, Atom::STRING);
+        assert_eq!(full.bytes_after, 0);
+        assert_eq!(full.value, data);
+
+        assert!(window
+            .get_full_property(property, Atom::STRING, false)
+            .unwrap()
+            .is_none());
+
+        window.destroy(WindowKind::Window).unwrap();
+    }
+
+    #[test]
+    #[serial]
+    fn test_displays_allocate_own_ids() {
+        let first = display::open(None).unwrap();
+        let second = display::open(None).unwrap();
+
+        let windows = [&first, &second, &first].map(|display| {
+            let root = display.default_root_window().unwrap();
+
+            root.create_window(WindowArguments {
+                depth: root.depth(),
+                x: 0,
+                y: 0,
+                width: 10,
+                height: 10,
+                border_width: 0,
+                class: WindowClass::InputOutput,
+                visual: root.visual(),
+                values: ValuesBuilder::new(vec![]),
+            })
+            .unwrap()
+        });
+
+        // ids from another connection would be rejected by the server
+        assert!(first.sync().is_ok());
+        assert!(second.sync().is_ok());
+
+        assert_ne!(windows[0].id(), windows[1].id());
+        assert_ne!(windows[0].id(), windows[2].id());
+    }
+
+    #[test]
+    #[serial]
+    fn test_display_screens() {
+        let display = display::open(None).unwrap();
+
+        assert!(!display.screens().is_empty());
+        assert!(display.screen(display.screens().len()).is_err());
+
+        let screen = display.default_screen().unwrap();
+        let root = display.default_root_window().unwrap();
+
+        assert_eq!(root.id(), screen.root());
+        assert_eq!(root.depth(), screen.root_depth());
+
+        let window = display.window_from_id(screen.root()).unwrap();
+
+        assert_eq!(window.depth(), screen.root_depth());
+        assert_eq!(window.visual().id, screen.root_visual().unwrap().id);
+    }
+
+    #[test]
+    #[serial]
+    fn test_display_setup_info() {
+        let display = display::open(None).unwrap();
+
+        let info = display.setup_info();
+
+        assert_eq!(info.protocol_major_version, 11);
+        assert!(!info.vendor.is_empty());
+        assert!(info.maximum_request_length >= 4096);
+        assert!(!info.pixmap_formats.is_empty());
+        assert!(info.min_keycode <= info.max_keycode);
+    }
+
+    #[test]
+    #[serial]
+    fn test_display_close() {
+        let display = display::open(None).unwrap();
+        let waiter = display.clone();
+
+        let handle = std::thread::spawn(move || waiter.next_event());
+
+        display.close().unwrap();
+
+        assert!(display.is_closed());
+        assert!(matches!(
+            handle.join().unwrap(),
+            Err(display::error::Error::ConnectionClosed)
+        ));
+        assert!(display.get_input_focus().is_err());
+    }
+
+    #[test]
+    #[serial]
+    fn test_window_outlives_display() {
+        let display = display::open(None).unwrap();
+
+        let root = display.default_root_window().unwrap();
+
+        drop(display);
+
+        // the window keeps the connection open after the display is dropped
+        let window = root
+            .create_window(WindowArguments {
+                depth: root.depth(),
+                x: 0,
+                y: 0,
+                width: 10,
+                height: 10,
+                border_width: 0,
+                class: WindowClass::InputOutput,
+                visual: root.visual(),
+                values: ValuesBuilder::new(vec![]),
+            })
+            .unwrap();
+
+        assert_eq!(window.get_geometry().unwrap().width, 10);
+        assert!(window.destroy(WindowKind::Window).is_ok());
+    }
+
+    #[test]
+    #[serial]
+    fn test_next_event_timeout() {
+        let display = display::open(None).unwrap();
+
+        assert!(display.try_next_event().unwrap().is_none());
+        assert!(display
+            .next_event_timeout(std::time::Duration::from_millis(50))
+            .unwrap()
+            .is_none());
+        assert!(!display
+            .wait_for_activity(Some(std::time::Duration::from_millis(50)))
+            .unwrap());
+    }
+
+    #[test]
+    #[serial]
+    fn test_event_fd() {
+        let display = display::open(None).unwrap();
+
+        let first = display.event_fd().unwrap();
+
+        assert!(first >= 0);
+        assert_eq!(display.clone().event_fd().unwrap(), first);
+    }
+
+    #[test]
+    #[serial]
+    fn test_single_threaded() {
+        let display = display::ConnectOptions::new()
+            .single_threaded(true)
+            .open()
+            .unwrap();
+
+        let root = display.default_root_window().unwrap();
+
+        display.get_input_focus().unwrap();
+        display.window_from_id(root.id()).unwrap();
+
+        assert!(display.try_next_event().unwrap().is_none());
+        assert!(display.event_fd().unwrap() >= 0);
+
+        let window = root
+            .create_window(WindowArguments {
+                depth: root.depth(),
+                x: 0,
+                y: 0,
+                width: 10,
+                height: 10,
+                border_width: 0,
+                class: WindowClass::InputOutput,
+                visual: root.visual(),
+                values: ValuesBuilder::new(vec![]),
+            })
+            .unwrap();
+
+        window.select_input(&[EventMask::StructureNotify]).unwrap();
+        window.map(WindowKind::Window).unwrap();
+        display.flush().unwrap();
+
+        // the map notify is read from the socket while the display waits for activity
+        assert!(display
+            .wait_for_activity(Some(std::time::Duration::from_secs(1)))
+            .unwrap());
+        assert!(display.try_next_event().unwrap().is_some());
+
+        display.close().unwrap();
+
+        assert!(display.try_next_event().is_err());
+    }
+}